@@ -0,0 +1,163 @@
+//! A block is the encoded data of an IPLD node together with its CID.
+//!
+//! A [`Block`] makes sure that the CID matches its data, it can only be created by encoding a
+//! value or by verifying the hash of existing data.
+use std::{error::Error, fmt};
+
+use cid::Cid;
+
+use crate::{
+    codec::Codec,
+    hash::{self, HashError, HashFunction},
+    ipld::Ipld,
+};
+
+/// A boxed error returned by a codec.
+type CodecError = Box<dyn Error + Send + Sync>;
+
+/// Error when creating or decoding a block.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BlockError {
+    /// Error when encoding or decoding the data failed.
+    Codec(CodecError),
+    /// Error when computing or verifying the hash failed.
+    Hash(HashError),
+    /// Error when the codec code of the CID doesn't match the codec.
+    CodecMismatch {
+        /// The codec code of the codec.
+        expected: u64,
+        /// The codec code of the CID.
+        found: u64,
+    },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Codec(error) => write!(f, "codec error: {}", error),
+            Self::Hash(error) => write!(f, "hash error: {}", error),
+            Self::CodecMismatch { expected, found } => write!(
+                f,
+                "codec mismatch: expected codec {:#x} but CID has {:#x}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl Error for BlockError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Codec(error) => Some(&**error),
+            Self::Hash(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<HashError> for BlockError {
+    fn from(error: HashError) -> Self {
+        Self::Hash(error)
+    }
+}
+
+/// The encoded data of an IPLD node together with its CID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    cid: Cid,
+    data: Vec<u8>,
+}
+
+impl Block {
+    /// Creates a block from a CID and the data it identifies.
+    ///
+    /// The hash of the data is verified, it's an error if it doesn't match the CID.
+    pub fn new(cid: Cid, data: Vec<u8>) -> Result<Self, BlockError> {
+        hash::verify(&cid, &data)?;
+        Ok(Self { cid, data })
+    }
+
+    /// Creates a block from a CID and the data it identifies, without verifying the hash.
+    ///
+    /// This is useful for data that is trusted, e.g. because it was verified before.
+    pub fn new_unchecked(cid: Cid, data: Vec<u8>) -> Self {
+        Self { cid, data }
+    }
+
+    /// Encodes a value with the codec `C` and computes its CIDv1 with the hash function `H`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ipld_core::{block::Block, hash::Sha2_256, ipld};
+    /// use serde_ipld_dagcbor::codec::DagCborCodec;
+    ///
+    /// let block = Block::encode::<DagCborCodec, Sha2_256, _>(&ipld!({})).unwrap();
+    /// assert_eq!(
+    ///     block.cid().to_string(),
+    ///     "bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua"
+    /// );
+    /// ```
+    pub fn encode<C, H, T>(value: &T) -> Result<Self, BlockError>
+    where
+        C: Codec<T>,
+        C::Error: Error + Send + Sync + 'static,
+        H: HashFunction,
+    {
+        let data = C::encode_to_vec(value).map_err(|error| BlockError::Codec(error.into()))?;
+        let cid = hash::cid_v1(C::CODE, H::CODE, &data)?;
+        Ok(Self { cid, data })
+    }
+
+    /// Decodes the data with the codec `C`.
+    ///
+    /// It's an error if the codec code of the CID doesn't match the one of the codec.
+    pub fn decode<C, T>(&self) -> Result<T, BlockError>
+    where
+        C: Codec<T>,
+        C::Error: Error + Send + Sync + 'static,
+    {
+        self.check_codec(C::CODE)?;
+        C::decode_from_slice(&self.data).map_err(|error| BlockError::Codec(error.into()))
+    }
+
+    /// Returns all links (CIDs) that the data contains, using the codec `C`.
+    ///
+    /// It's an error if the codec code of the CID doesn't match the one of the codec.
+    pub fn links<'a, C>(&'a self) -> Result<impl Iterator<Item = Cid> + 'a, BlockError>
+    where
+        C: Codec<Ipld> + 'a,
+        C::LinksError: Error + Send + Sync + 'static,
+    {
+        self.check_codec(C::CODE)?;
+        C::links(&self.data).map_err(|error| BlockError::Codec(error.into()))
+    }
+
+    /// Returns an error if the codec code of the CID isn't the given one.
+    fn check_codec(&self, code: u64) -> Result<(), BlockError> {
+        if self.cid.codec() == code {
+            Ok(())
+        } else {
+            Err(BlockError::CodecMismatch {
+                expected: code,
+                found: self.cid.codec(),
+            })
+        }
+    }
+
+    /// Returns the CID of the block.
+    pub fn cid(&self) -> &Cid {
+        &self.cid
+    }
+
+    /// Returns the encoded data of the block.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the CID and the encoded data of the block.
+    pub fn into_inner(self) -> (Cid, Vec<u8>) {
+        (self.cid, self.data)
+    }
+}
@@ -0,0 +1,280 @@
+//! Storage for blocks, the encoded data of IPLD nodes, keyed by their CID.
+//!
+//! The [`Blockstore`] trait is a common interface for block storage. [`MemoryBlockstore`] keeps
+//! blocks in memory, [`FsBlockstore`] stores one file per block on disk.
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::convert::Infallible;
+
+use cid::Cid;
+
+/// Storage for blocks.
+///
+/// A blockstore doesn't verify that the data matches the CID, it's up to the caller to store
+/// only valid blocks.
+pub trait Blockstore {
+    /// The error that is returned if an operation on the store fails.
+    type Error;
+
+    /// Returns the data of the block with the given CID, or `None` if it's not stored.
+    fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>, Self::Error>;
+
+    /// Stores a block, an already stored block with the same CID is replaced.
+    fn put(&mut self, cid: &Cid, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Returns whether the block with the given CID is stored.
+    fn has(&self, cid: &Cid) -> Result<bool, Self::Error> {
+        Ok(self.get(cid)?.is_some())
+    }
+
+    /// Removes the block with the given CID, it's not an error if it isn't stored.
+    fn delete(&mut self, cid: &Cid) -> Result<(), Self::Error>;
+
+    /// Stores several blocks.
+    ///
+    /// The default implementation calls [`Blockstore::put`] for every block and stops at the
+    /// first error.
+    fn put_many<I>(&mut self, blocks: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = (Cid, Vec<u8>)>,
+    {
+        for (cid, data) in blocks {
+            self.put(&cid, &data)?;
+        }
+        Ok(())
+    }
+}
+
+/// A blockstore that keeps all blocks in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryBlockstore {
+    blocks: BTreeMap<Cid, Vec<u8>>,
+}
+
+impl MemoryBlockstore {
+    /// Creates an empty blockstore.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of stored blocks.
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns whether no blocks are stored.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns an iterator over the CIDs of the stored blocks.
+    pub fn cids(&self) -> impl Iterator<Item = &Cid> {
+        self.blocks.keys()
+    }
+}
+
+impl Blockstore for MemoryBlockstore {
+    type Error = Infallible;
+
+    fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>, Self::Error> {
+        Ok(self.blocks.get(cid).cloned())
+    }
+
+    fn put(&mut self, cid: &Cid, data: &[u8]) -> Result<(), Self::Error> {
+        self.blocks.insert(*cid, data.to_vec());
+        Ok(())
+    }
+
+    fn has(&self, cid: &Cid) -> Result<bool, Self::Error> {
+        Ok(self.blocks.contains_key(cid))
+    }
+
+    fn delete(&mut self, cid: &Cid) -> Result<(), Self::Error> {
+        self.blocks.remove(cid);
+        Ok(())
+    }
+
+    fn put_many<I>(&mut self, blocks: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = (Cid, Vec<u8>)>,
+    {
+        self.blocks.extend(blocks);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+pub use fs::FsBlockstore;
+
+#[cfg(feature = "std")]
+mod fs {
+    use std::{
+        fs, io,
+        path::{Path, PathBuf},
+        process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use cid::{multibase::Base, Cid};
+
+    use super::Blockstore;
+
+    /// Counter to make the names of temporary files unique within a process.
+    static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    /// A blockstore that stores every block in a separate file.
+    ///
+    /// The file name is the lowercase base32 encoding of the binary CID, so that it works on
+    /// case-insensitive file systems. Files are sharded into directories named after the
+    /// next-to-last two characters of the file name, this keeps the number of entries per
+    /// directory manageable.
+    ///
+    /// Blocks are first written to a temporary file which is then renamed, so that a block is
+    /// either stored completely or not at all.
+    #[derive(Clone, Debug)]
+    pub struct FsBlockstore {
+        root: PathBuf,
+    }
+
+    impl FsBlockstore {
+        /// Opens a blockstore at the given directory, it's created if it doesn't exist.
+        pub fn new<P: Into<PathBuf>>(root: P) -> io::Result<Self> {
+            let root = root.into();
+            fs::create_dir_all(&root)?;
+            Ok(Self { root })
+        }
+
+        /// Returns the root directory of the blockstore.
+        pub fn root(&self) -> &Path {
+            &self.root
+        }
+
+        /// Returns the path of the file the block with the given CID is stored in.
+        pub fn path(&self, cid: &Cid) -> PathBuf {
+            let name = Base::Base32Lower.encode(cid.to_bytes());
+            let shard = &name[name.len() - 3..name.len() - 1];
+            self.root.join(shard).join(name)
+        }
+    }
+
+    impl Blockstore for FsBlockstore {
+        type Error = io::Error;
+
+        fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>, Self::Error> {
+            match fs::read(self.path(cid)) {
+                Ok(data) => Ok(Some(data)),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(error) => Err(error),
+            }
+        }
+
+        fn put(&mut self, cid: &Cid, data: &[u8]) -> Result<(), Self::Error> {
+            let path = self.path(cid);
+            let dir = path.parent().expect("block path has a shard directory");
+            fs::create_dir_all(dir)?;
+            let tmp = dir.join(format!(
+                ".{}.{}.{}.tmp",
+                path.file_name()
+                    .expect("block path has a file name")
+                    .to_string_lossy(),
+                process::id(),
+                TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            if let Err(error) = fs::write(&tmp, data).and_then(|_| fs::rename(&tmp, &path)) {
+                let _ = fs::remove_file(&tmp);
+                return Err(error);
+            }
+            Ok(())
+        }
+
+        fn has(&self, cid: &Cid) -> Result<bool, Self::Error> {
+            self.path(cid).try_exists()
+        }
+
+        fn delete(&mut self, cid: &Cid) -> Result<(), Self::Error> {
+            match fs::remove_file(self.path(cid)) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+                _ => Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use cid::multihash::Multihash;
+
+    use super::*;
+
+    fn cid(index: u8) -> Cid {
+        Cid::new_v1(0x55, Multihash::wrap(0x12, &[index; 32]).unwrap())
+    }
+
+    fn check_blockstore<B: Blockstore>(store: &mut B)
+    where
+        B::Error: core::fmt::Debug,
+    {
+        assert_eq!(store.get(&cid(1)).unwrap(), None);
+        assert!(!store.has(&cid(1)).unwrap());
+
+        store.put(&cid(1), b"one").unwrap();
+        assert_eq!(store.get(&cid(1)).unwrap(), Some(b"one".to_vec()));
+        assert!(store.has(&cid(1)).unwrap());
+
+        store.put(&cid(1), b"replaced").unwrap();
+        assert_eq!(store.get(&cid(1)).unwrap(), Some(b"replaced".to_vec()));
+
+        store
+            .put_many(vec![(cid(2), b"two".to_vec()), (cid(3), b"three".to_vec())])
+            .unwrap();
+        assert_eq!(store.get(&cid(2)).unwrap(), Some(b"two".to_vec()));
+        assert_eq!(store.get(&cid(3)).unwrap(), Some(b"three".to_vec()));
+
+        store.delete(&cid(1)).unwrap();
+        assert!(!store.has(&cid(1)).unwrap());
+        // Deleting a block that isn't stored is not an error.
+        store.delete(&cid(1)).unwrap();
+        assert!(store.has(&cid(2)).unwrap());
+    }
+
+    #[test]
+    fn test_memory_blockstore() {
+        let mut store = MemoryBlockstore::new();
+        check_blockstore(&mut store);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.cids().collect::<Vec<_>>(), [&cid(2), &cid(3)]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_fs_blockstore() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = FsBlockstore::new(dir.path().join("blocks")).unwrap();
+        check_blockstore(&mut store);
+
+        // The blocks are sharded and no temporary files are left behind.
+        let path = store.path(&cid(2));
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(name, name.to_lowercase());
+        assert_eq!(
+            path.parent()
+                .unwrap()
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            &name[name.len() - 3..name.len() - 1]
+        );
+        let files: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert!(files.iter().any(|file| file == name));
+        assert!(files.iter().all(|file| !file.ends_with(".tmp")));
+
+        // The blocks are persisted.
+        let store = FsBlockstore::new(dir.path().join("blocks")).unwrap();
+        assert_eq!(store.get(&cid(3)).unwrap(), Some(b"three".to_vec()));
+    }
+}
@@ -0,0 +1,381 @@
+//! A minimal DAG-CBOR implementation for [`Ipld`], it's used for the CAR headers.
+//!
+//! A full DAG-CBOR codec is provided by the `serde_ipld_dagcbor` crate, which cannot be used here
+//! as it depends on this crate.
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use cid::Cid;
+
+use crate::ipld::Ipld;
+
+/// The CBOR tag of IPLD links.
+const CID_TAG: u64 = 42;
+
+/// The maximum nesting depth when decoding, it's far more than a CAR header needs.
+const MAX_DEPTH: usize = 64;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_STRING: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+const FLOAT64: u8 = 0xfb;
+
+/// Writes the head of a data item, the major type and its argument in the shortest form.
+fn write_head(output: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value < 24 {
+        output.push(major | value as u8);
+    } else if let Ok(value) = u8::try_from(value) {
+        output.extend([major | 24, value]);
+    } else if let Ok(value) = u16::try_from(value) {
+        output.push(major | 25);
+        output.extend(value.to_be_bytes());
+    } else if let Ok(value) = u32::try_from(value) {
+        output.push(major | 26);
+        output.extend(value.to_be_bytes());
+    } else {
+        output.push(major | 27);
+        output.extend(value.to_be_bytes());
+    }
+}
+
+/// Encodes IPLD as DAG-CBOR.
+pub(crate) fn encode(ipld: &Ipld, output: &mut Vec<u8>) -> Result<(), String> {
+    match ipld {
+        Ipld::Null => output.push(NULL),
+        Ipld::Bool(false) => output.push(FALSE),
+        Ipld::Bool(true) => output.push(TRUE),
+        Ipld::Integer(integer) => {
+            if let Ok(value) = u64::try_from(*integer) {
+                write_head(output, MAJOR_UNSIGNED, value);
+            } else if let Ok(value) = u64::try_from(-1 - *integer) {
+                write_head(output, MAJOR_NEGATIVE, value);
+            } else {
+                return Err(format!("integer {} is out of range", integer));
+            }
+        }
+        Ipld::Float(float) => {
+            if !float.is_finite() {
+                return Err(format!("float {} is not finite", float));
+            }
+            output.push(FLOAT64);
+            output.extend(float.to_be_bytes());
+        }
+        Ipld::String(string) => {
+            write_head(output, MAJOR_STRING, string.len() as u64);
+            output.extend(string.as_bytes());
+        }
+        Ipld::Bytes(bytes) => {
+            write_head(output, MAJOR_BYTES, bytes.len() as u64);
+            output.extend(bytes);
+        }
+        Ipld::List(list) => {
+            write_head(output, MAJOR_ARRAY, list.len() as u64);
+            for item in list {
+                encode(item, output)?;
+            }
+        }
+        Ipld::Map(map) => {
+            write_head(output, MAJOR_MAP, map.len() as u64);
+            // DAG-CBOR sorts the keys by length first, then bytewise.
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+            for (key, value) in entries {
+                write_head(output, MAJOR_STRING, key.len() as u64);
+                output.extend(key.as_bytes());
+                encode(value, output)?;
+            }
+        }
+        Ipld::Link(cid) => {
+            write_head(output, MAJOR_TAG, CID_TAG);
+            let bytes = cid.to_bytes();
+            // The CID is prefixed with the multibase identity prefix.
+            write_head(output, MAJOR_BYTES, bytes.len() as u64 + 1);
+            output.push(0x00);
+            output.extend(bytes);
+        }
+    }
+    Ok(())
+}
+
+/// Decodes DAG-CBOR into IPLD, the input must contain exactly one data item.
+pub(crate) fn decode(input: &[u8]) -> Result<Ipld, String> {
+    let mut decoder = Decoder { input, offset: 0 };
+    let ipld = decoder.decode(0)?;
+    if decoder.offset != input.len() {
+        return Err(format!("trailing data at offset {}", decoder.offset));
+    }
+    Ok(ipld)
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let remaining = &self.input[self.offset..];
+        if remaining.len() < len {
+            return Err(format!(
+                "unexpected end of data at offset {}",
+                self.input.len()
+            ));
+        }
+        self.offset += len;
+        Ok(&remaining[..len])
+    }
+
+    /// Reads the head of a data item, it returns the major type and its argument.
+    fn head(&mut self) -> Result<(u8, u64), String> {
+        let offset = self.offset;
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let value = match initial & 0x1f {
+            info @ 0..=23 => u64::from(info),
+            24 => u64::from(self.take(1)?[0]),
+            25 => u64::from(u16::from_be_bytes(self.take(2)?.try_into().unwrap())),
+            26 => u64::from(u32::from_be_bytes(self.take(4)?.try_into().unwrap())),
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            _ => {
+                return Err(format!(
+                    "unsupported additional information {:#x} at offset {}",
+                    initial & 0x1f,
+                    offset
+                ))
+            }
+        };
+        let minimal = match initial & 0x1f {
+            24 => value >= 24,
+            25 => value > u64::from(u8::MAX),
+            26 => value > u64::from(u16::MAX),
+            27 => value > u64::from(u32::MAX),
+            _ => true,
+        };
+        // Floats use the same additional information, but aren't integers.
+        if !minimal && major != MAJOR_SIMPLE {
+            return Err(format!(
+                "integer is not minimally encoded at offset {}",
+                offset
+            ));
+        }
+        Ok((major, value))
+    }
+
+    /// Reads a length, it's an error if there isn't at least that much data left.
+    fn len(&mut self, value: u64) -> Result<usize, String> {
+        match usize::try_from(value) {
+            Ok(len) if len <= self.input.len() - self.offset => Ok(len),
+            _ => Err(format!(
+                "length {} at offset {} exceeds the data",
+                value, self.offset
+            )),
+        }
+    }
+
+    fn string(&mut self, value: u64) -> Result<String, String> {
+        let offset = self.offset;
+        let len = self.len(value)?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| format!("invalid UTF-8 string at offset {}", offset))
+    }
+
+    fn decode(&mut self, depth: usize) -> Result<Ipld, String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "maximum nesting depth of {} exceeded at offset {}",
+                MAX_DEPTH, self.offset
+            ));
+        }
+        let offset = self.offset;
+        let (major, value) = self.head()?;
+        let ipld = match major {
+            MAJOR_UNSIGNED => Ipld::Integer(i128::from(value)),
+            MAJOR_NEGATIVE => Ipld::Integer(-1 - i128::from(value)),
+            MAJOR_BYTES => {
+                let len = self.len(value)?;
+                Ipld::Bytes(self.take(len)?.to_vec())
+            }
+            MAJOR_STRING => Ipld::String(self.string(value)?),
+            MAJOR_ARRAY => {
+                // Every item needs at least one byte.
+                let len = self.len(value)?;
+                let mut list = Vec::with_capacity(len);
+                for _ in 0..len {
+                    list.push(self.decode(depth + 1)?);
+                }
+                Ipld::List(list)
+            }
+            MAJOR_MAP => {
+                let len = self.len(value)?;
+                let mut map = BTreeMap::new();
+                let mut previous: Option<String> = None;
+                for _ in 0..len {
+                    let key_offset = self.offset;
+                    let key = match self.head()? {
+                        (MAJOR_STRING, value) => self.string(value)?,
+                        _ => {
+                            return Err(format!("map key at offset {} is not a string", key_offset))
+                        }
+                    };
+                    // Keys are sorted by length first, then bytewise, the same way as they are
+                    // encoded.
+                    if let Some(previous) = &previous {
+                        match (previous.len(), previous.as_bytes())
+                            .cmp(&(key.len(), key.as_bytes()))
+                        {
+                            Ordering::Less => {}
+                            Ordering::Equal => {
+                                return Err(format!("duplicate map key at offset {}", key_offset))
+                            }
+                            Ordering::Greater => {
+                                return Err(format!(
+                                    "map key at offset {} is not sorted",
+                                    key_offset
+                                ))
+                            }
+                        }
+                    }
+                    let value = self.decode(depth + 1)?;
+                    previous = Some(key.clone());
+                    map.insert(key, value);
+                }
+                Ipld::Map(map)
+            }
+            MAJOR_TAG if value == CID_TAG => {
+                let bytes_offset = self.offset;
+                let bytes = match self.head()? {
+                    (MAJOR_BYTES, value) => {
+                        let len = self.len(value)?;
+                        self.take(len)?
+                    }
+                    _ => {
+                        return Err(format!(
+                            "CID at offset {} is not a byte string",
+                            bytes_offset
+                        ))
+                    }
+                };
+                match bytes.split_first() {
+                    Some((0x00, cid)) => {
+                        Ipld::Link(Cid::try_from(cid).map_err(|error| {
+                            format!("invalid CID at offset {}: {}", offset, error)
+                        })?)
+                    }
+                    _ => return Err(format!("CID at offset {} has no identity prefix", offset)),
+                }
+            }
+            MAJOR_TAG => return Err(format!("unsupported tag {} at offset {}", value, offset)),
+            _ => match self.input[offset] {
+                FALSE => Ipld::Bool(false),
+                TRUE => Ipld::Bool(true),
+                NULL => Ipld::Null,
+                FLOAT64 => {
+                    let float = f64::from_bits(value);
+                    if !float.is_finite() {
+                        return Err(format!("float at offset {} is not a finite number", offset));
+                    }
+                    Ipld::Float(float)
+                }
+                other => {
+                    return Err(format!(
+                        "unsupported simple value or float {:#x} at offset {}",
+                        other, offset
+                    ))
+                }
+            },
+        };
+        Ok(ipld)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipld;
+
+    fn roundtrip(ipld: Ipld) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encode(&ipld, &mut bytes).unwrap();
+        assert_eq!(decode(&bytes).unwrap(), ipld);
+        bytes
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let cid =
+            Cid::try_from("bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily").unwrap();
+        roundtrip(ipld!({
+            "null": null,
+            "bool": [true, false],
+            "int": [0, 23, 24, 255, 256, 65536, u64::MAX, -1, -25, -i128::from(u64::MAX) - 1],
+            "float": 1.5,
+            "string": "ünïcödé",
+            "bytes": Ipld::Bytes(vec![1, 2, 3]),
+            "link": cid,
+        }));
+    }
+
+    #[test]
+    fn test_canonical() {
+        assert_eq!(roundtrip(ipld!(0)), [0x00]);
+        assert_eq!(roundtrip(ipld!(24)), [0x18, 0x18]);
+        assert_eq!(roundtrip(ipld!(-1)), [0x20]);
+        assert_eq!(roundtrip(ipld!(1000)), [0x19, 0x03, 0xe8]);
+        // Keys are sorted by length first.
+        assert_eq!(
+            roundtrip(ipld!({"bb": 1, "a": 2})),
+            [0xa2, 0x61, b'a', 0x02, 0x62, b'b', b'b', 0x01]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut bytes = Vec::new();
+        assert!(encode(&Ipld::Float(f64::NAN), &mut bytes).is_err());
+        assert!(encode(&Ipld::Integer(i128::MAX), &mut bytes).is_err());
+
+        // Not minimally encoded.
+        assert!(decode(&[0x18, 0x01]).is_err());
+        // Indefinite length.
+        assert!(decode(&[0x9f, 0xff]).is_err());
+        // Truncated.
+        assert!(decode(&[0x82, 0x01]).is_err());
+        // Length exceeding the data.
+        assert!(decode(&[0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).is_err());
+        // Trailing data.
+        assert!(decode(&[0x01, 0x01]).is_err());
+        // Non-string key.
+        assert!(decode(&[0xa1, 0x01, 0x01]).is_err());
+        // Unsupported tag.
+        assert!(decode(&[0xc1, 0x01]).is_err());
+        // 32-bit float.
+        assert!(decode(&[0xfa, 0x00, 0x00, 0x00, 0x00]).is_err());
+        // Too deeply nested.
+        assert!(decode(&[0x81; 100]).is_err());
+        // NaN and infinity.
+        assert!(decode(&[0xfb, 0x7f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).is_err());
+        assert!(decode(&[0xfb, 0x7f, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).is_err());
+        assert!(decode(&[0xfb, 0xff, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).is_err());
+        // Map keys not sorted by length first: {"bb": 1, "a": 2}.
+        assert!(decode(&[0xa2, 0x62, 0x62, 0x62, 0x01, 0x61, 0x61, 0x02]).is_err());
+        // Map keys of the same length not sorted bytewise: {"b": 1, "a": 2}.
+        assert!(decode(&[0xa2, 0x61, 0x62, 0x01, 0x61, 0x61, 0x02]).is_err());
+        // Duplicate map keys: {"a": 1, "a": 2}.
+        assert!(decode(&[0xa2, 0x61, 0x61, 0x01, 0x61, 0x61, 0x02]).is_err());
+        // Sorted map keys: {"b": 1, "aa": 2}.
+        assert_eq!(
+            decode(&[0xa2, 0x61, 0x62, 0x01, 0x62, 0x61, 0x61, 0x02]).unwrap(),
+            ipld!({"b": 1, "aa": 2})
+        );
+    }
+}
@@ -0,0 +1,264 @@
+//! Content Addressable aRchives (CAR).
+//!
+//! This is an implementation of the [CARv1] format, a container for a sequence of blocks, and of
+//! the [CARv2] format, which wraps a CARv1 and adds an index for random access to the blocks.
+//!
+//! [CARv1]: https://ipld.io/specs/transport/car/carv1/
+//! [CARv2]: https://ipld.io/specs/transport/car/carv2/
+mod cbor;
+mod v1;
+mod v2;
+
+use std::{fmt, io};
+
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+
+use crate::serde::{from_ipld, to_ipld};
+
+pub use v1::{CarReader, CarWriter};
+pub use v2::{CarV2Header, CarV2Reader, CarV2Writer, IndexCodec};
+
+/// The maximum size of the header and of a section, larger ones are considered invalid in order
+/// to protect against memory exhaustion.
+pub const DEFAULT_MAX_SECTION_SIZE: usize = 32 * 1024 * 1024;
+
+/// The maximum number of bytes of a varint, as defined by the multiformats specification.
+const MAX_VARINT_LEN: usize = 9;
+
+/// Error when reading or writing a CAR.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CarError {
+    /// Error when the underlying reader or writer failed.
+    Io(io::Error),
+    /// Error when the data ended in the middle of an item.
+    UnexpectedEof {
+        /// The offset where more data was expected.
+        offset: u64,
+    },
+    /// Error when a varint is invalid, it's too long or not minimally encoded.
+    InvalidVarint {
+        /// The offset of the varint.
+        offset: u64,
+    },
+    /// Error when the header is invalid.
+    InvalidHeader(String),
+    /// Error when the version of the CAR isn't supported.
+    UnsupportedVersion(u64),
+    /// Error when a section has a length of zero.
+    EmptySection {
+        /// The offset of the section.
+        offset: u64,
+    },
+    /// Error when the header or a section is larger than the maximum size.
+    SectionTooLarge {
+        /// The offset of the header or section.
+        offset: u64,
+        /// The size of the header or section.
+        size: u64,
+    },
+    /// Error when the CID of a section is invalid.
+    InvalidCid {
+        /// The offset of the CID.
+        offset: u64,
+        /// The error returned when parsing the CID.
+        error: cid::Error,
+    },
+    /// Error when the index of a CARv2 is invalid.
+    InvalidIndex(String),
+    /// Error when the codec of the index of a CARv2 isn't supported.
+    UnsupportedIndex(u64),
+}
+
+impl fmt::Display for CarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            Self::UnexpectedEof { offset } => {
+                write!(f, "unexpected end of data at offset {}", offset)
+            }
+            Self::InvalidVarint { offset } => write!(f, "invalid varint at offset {}", offset),
+            Self::InvalidHeader(message) => write!(f, "invalid header: {}", message),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported CAR version: {}", version),
+            Self::EmptySection { offset } => write!(f, "empty section at offset {}", offset),
+            Self::SectionTooLarge { offset, size } => {
+                write!(
+                    f,
+                    "section at offset {} is too large: {} bytes",
+                    offset, size
+                )
+            }
+            Self::InvalidCid { offset, error } => {
+                write!(f, "invalid CID at offset {}: {}", offset, error)
+            }
+            Self::InvalidIndex(message) => write!(f, "invalid index: {}", message),
+            Self::UnsupportedIndex(code) => write!(f, "unsupported index codec: {:#x}", code),
+        }
+    }
+}
+
+impl std::error::Error for CarError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::InvalidCid { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CarError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// The header of a CARv1.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarHeader {
+    /// The version of the CAR format.
+    pub version: u64,
+    /// The roots of the DAGs the CAR contains.
+    pub roots: Vec<Cid>,
+}
+
+impl CarHeader {
+    /// Creates a CARv1 header with the given roots.
+    pub fn new(roots: Vec<Cid>) -> Self {
+        Self { version: 1, roots }
+    }
+
+    /// Encodes the header as DAG-CBOR.
+    fn encode(&self) -> Result<Vec<u8>, CarError> {
+        let ipld = to_ipld(self).map_err(|error| CarError::InvalidHeader(error.to_string()))?;
+        let mut bytes = Vec::new();
+        cbor::encode(&ipld, &mut bytes).map_err(CarError::InvalidHeader)?;
+        Ok(bytes)
+    }
+
+    /// Decodes a DAG-CBOR encoded header, it's not checked whether the version is supported.
+    fn decode(bytes: &[u8]) -> Result<Self, CarError> {
+        let ipld = cbor::decode(bytes).map_err(CarError::InvalidHeader)?;
+        from_ipld(ipld).map_err(|error| CarError::InvalidHeader(error.to_string()))
+    }
+}
+
+/// The part of a header that is common to all CAR versions.
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u64,
+}
+
+/// Decodes the version of a DAG-CBOR encoded header, other fields are ignored.
+fn decode_version(bytes: &[u8]) -> Result<u64, CarError> {
+    let ipld = cbor::decode(bytes).map_err(CarError::InvalidHeader)?;
+    let header: VersionHeader =
+        from_ipld(ipld).map_err(|error| CarError::InvalidHeader(error.to_string()))?;
+    Ok(header.version)
+}
+
+/// Encodes an unsigned varint.
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+/// Reads an unsigned varint, `offset` is the position of the reader, it's advanced by the
+/// number of bytes read.
+///
+/// Returns `None` if the reader is at its end before the first byte.
+fn read_varint<R: io::BufRead>(reader: &mut R, offset: &mut u64) -> Result<Option<u64>, CarError> {
+    let start = *offset;
+    let mut value = 0u64;
+    for index in 0..MAX_VARINT_LEN {
+        let byte = match reader.fill_buf()?.first() {
+            Some(byte) => *byte,
+            None if index == 0 => return Ok(None),
+            None => return Err(CarError::UnexpectedEof { offset: *offset }),
+        };
+        reader.consume(1);
+        *offset += 1;
+        value |= u64::from(byte & 0x7f) << (index * 7);
+        if byte & 0x80 == 0 {
+            // A trailing zero byte means that the varint isn't minimally encoded.
+            if byte == 0 && index > 0 {
+                return Err(CarError::InvalidVarint { offset: start });
+            }
+            return Ok(Some(value));
+        }
+    }
+    Err(CarError::InvalidVarint { offset: start })
+}
+
+/// Reads exactly `len` bytes, `offset` is the position of the reader.
+fn read_exact<R: io::Read>(
+    reader: &mut R,
+    len: usize,
+    offset: &mut u64,
+) -> Result<Vec<u8>, CarError> {
+    let mut bytes = Vec::new();
+    let read = io::Read::read_to_end(&mut io::Read::take(reader, len as u64), &mut bytes)?;
+    *offset += read as u64;
+    if read != len {
+        return Err(CarError::UnexpectedEof { offset: *offset });
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(value: u64) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, value);
+        bytes
+    }
+
+    #[test]
+    fn test_varint_roundtrip() {
+        for value in [
+            0,
+            1,
+            127,
+            128,
+            300,
+            16384,
+            u64::from(u32::MAX),
+            (1 << 63) - 1,
+        ] {
+            let bytes = varint(value);
+            let mut offset = 0;
+            assert_eq!(
+                read_varint(&mut &bytes[..], &mut offset).unwrap(),
+                Some(value)
+            );
+            assert_eq!(offset, bytes.len() as u64);
+        }
+        assert_eq!(varint(300), [0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_varint_invalid() {
+        let mut offset = 0;
+        assert!(read_varint(&mut &[][..], &mut offset).unwrap().is_none());
+        assert!(matches!(
+            read_varint(&mut &[0x80][..], &mut offset),
+            Err(CarError::UnexpectedEof { offset: 1 })
+        ));
+        // Not minimally encoded.
+        assert!(matches!(
+            read_varint(&mut &[0x81, 0x00][..], &mut 0),
+            Err(CarError::InvalidVarint { offset: 0 })
+        ));
+        // Longer than 9 bytes.
+        assert!(matches!(
+            read_varint(&mut &[0xff; 10][..], &mut 0),
+            Err(CarError::InvalidVarint { offset: 0 })
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! Reading and writing of CARv1.
+use std::io::{BufRead, Write};
+
+use cid::Cid;
+
+use super::{read_exact, read_varint, write_varint, CarError, CarHeader, DEFAULT_MAX_SECTION_SIZE};
+
+/// Checks the size of the header or a section.
+fn section_size(size: u64, offset: u64, max: usize) -> Result<usize, CarError> {
+    match usize::try_from(size) {
+        Ok(0) => Err(CarError::EmptySection { offset }),
+        Ok(size) if size <= max => Ok(size),
+        _ => Err(CarError::SectionTooLarge { offset, size }),
+    }
+}
+
+/// Reads the encoded header of a CAR without decoding it, `offset` is the position of the reader.
+pub(crate) fn read_header_bytes<R: BufRead>(
+    reader: &mut R,
+    offset: &mut u64,
+) -> Result<Vec<u8>, CarError> {
+    let start = *offset;
+    let size = read_varint(reader, offset)?.ok_or(CarError::UnexpectedEof { offset: start })?;
+    let size = section_size(size, start, DEFAULT_MAX_SECTION_SIZE)?;
+    read_exact(reader, size, offset)
+}
+
+/// Reads the header of a CARv1, `offset` is the position of the reader.
+pub(crate) fn read_header<R: BufRead>(
+    reader: &mut R,
+    offset: &mut u64,
+) -> Result<CarHeader, CarError> {
+    let header = CarHeader::decode(&read_header_bytes(reader, offset)?)?;
+    if header.version != 1 {
+        return Err(CarError::UnsupportedVersion(header.version));
+    }
+    Ok(header)
+}
+
+/// Reads a section of a CARv1, `offset` is the position of the reader.
+///
+/// Returns `None` if the reader is at its end.
+pub(crate) fn read_section<R: BufRead>(
+    reader: &mut R,
+    offset: &mut u64,
+    max_size: usize,
+) -> Result<Option<(Cid, Vec<u8>)>, CarError> {
+    let start = *offset;
+    let size = match read_varint(reader, offset)? {
+        Some(size) => section_size(size, start, max_size)?,
+        None => return Ok(None),
+    };
+    let section = read_exact(reader, size, offset)?;
+    let cid_offset = *offset - size as u64;
+    let mut data = &section[..];
+    let cid = Cid::read_bytes(&mut data).map_err(|error| CarError::InvalidCid {
+        offset: cid_offset,
+        error,
+    })?;
+    let data = data.to_vec();
+    Ok(Some((cid, data)))
+}
+
+/// Writes a section of a CARv1.
+pub(crate) fn write_section<W: Write>(
+    writer: &mut W,
+    cid: &Cid,
+    data: &[u8],
+) -> Result<u64, CarError> {
+    let cid = cid.to_bytes();
+    let mut section = Vec::with_capacity(cid.len() + data.len() + 10);
+    write_varint(&mut section, (cid.len() + data.len()) as u64);
+    section.extend(cid);
+    writer.write_all(&section)?;
+    writer.write_all(data)?;
+    Ok((section.len() + data.len()) as u64)
+}
+
+/// Encodes the header of a CARv1 together with its length prefix.
+pub(crate) fn encode_header(header: &CarHeader) -> Result<Vec<u8>, CarError> {
+    let header = header.encode()?;
+    let mut bytes = Vec::with_capacity(header.len() + 10);
+    write_varint(&mut bytes, header.len() as u64);
+    bytes.extend(header);
+    Ok(bytes)
+}
+
+/// A streaming reader of a CARv1.
+///
+/// The header is read when the reader is created, the blocks are returned by iterating over the
+/// reader. The iteration ends after the first error.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{car::{CarReader, CarWriter}, cid::Cid};
+///
+/// let cid = Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+/// let mut writer = CarWriter::new(Vec::new(), vec![cid]).unwrap();
+/// writer.write(&cid, b"data").unwrap();
+/// let car = writer.finish().unwrap();
+///
+/// let reader = CarReader::new(&car[..]).unwrap();
+/// assert_eq!(reader.header().roots, [cid]);
+/// let blocks = reader.collect::<Result<Vec<_>, _>>().unwrap();
+/// assert_eq!(blocks, [(cid, b"data".to_vec())]);
+/// ```
+#[derive(Debug)]
+pub struct CarReader<R> {
+    reader: R,
+    header: CarHeader,
+    offset: u64,
+    max_section_size: usize,
+    done: bool,
+}
+
+impl<R: BufRead> CarReader<R> {
+    /// Creates a reader and reads the header.
+    pub fn new(mut reader: R) -> Result<Self, CarError> {
+        let mut offset = 0;
+        let header = read_header(&mut reader, &mut offset)?;
+        Ok(Self {
+            reader,
+            header,
+            offset,
+            max_section_size: DEFAULT_MAX_SECTION_SIZE,
+            done: false,
+        })
+    }
+
+    /// Sets the maximum size of a section, the default is [`DEFAULT_MAX_SECTION_SIZE`].
+    pub fn max_section_size(mut self, max: usize) -> Self {
+        self.max_section_size = max;
+        self
+    }
+
+    /// Returns the header.
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for CarReader<R> {
+    type Item = Result<(Cid, Vec<u8>), CarError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let section = read_section(&mut self.reader, &mut self.offset, self.max_section_size);
+        if !matches!(section, Ok(Some(_))) {
+            self.done = true;
+        }
+        section.transpose()
+    }
+}
+
+/// A writer of a CARv1.
+#[derive(Debug)]
+pub struct CarWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CarWriter<W> {
+    /// Creates a writer and writes the header with the given roots.
+    pub fn new(writer: W, roots: Vec<Cid>) -> Result<Self, CarError> {
+        Self::with_header(writer, &CarHeader::new(roots))
+    }
+
+    /// Creates a writer and writes the given header.
+    pub fn with_header(mut writer: W, header: &CarHeader) -> Result<Self, CarError> {
+        if header.version != 1 {
+            return Err(CarError::UnsupportedVersion(header.version));
+        }
+        writer.write_all(&encode_header(header)?)?;
+        Ok(Self { writer })
+    }
+
+    /// Writes a block.
+    ///
+    /// The data isn't verified against the CID.
+    pub fn write(&mut self, cid: &Cid, data: &[u8]) -> Result<(), CarError> {
+        write_section(&mut self.writer, cid, data)?;
+        Ok(())
+    }
+
+    /// Flushes the writer and returns it.
+    pub fn finish(mut self) -> Result<W, CarError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
@@ -0,0 +1,556 @@
+//! Reading and writing of CARv2.
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Seek, SeekFrom, Write},
+};
+
+use cid::{multihash::Multihash, Cid};
+
+use super::{
+    decode_version, read_exact, read_varint, v1, write_varint, CarError, CarHeader, CarReader,
+    DEFAULT_MAX_SECTION_SIZE,
+};
+
+/// The pragma at the start of every CARv2, it's a CARv1 style header with version 2.
+const PRAGMA: [u8; 11] = [
+    0x0a, 0xa1, 0x67, b'v', b'e', b'r', b's', b'i', b'o', b'n', 0x02,
+];
+
+/// The size of the header that follows the pragma.
+const HEADER_LEN: usize = 40;
+
+/// The offset of the data payload if it directly follows the header.
+const DATA_OFFSET: u64 = (PRAGMA.len() + HEADER_LEN) as u64;
+
+/// The characteristics bit that is set if the index contains all blocks.
+const FULLY_INDEXED: u8 = 0x80;
+
+/// The header of a CARv2, it follows the pragma.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CarV2Header {
+    /// A bitfield of the characteristics of the CAR.
+    pub characteristics: [u8; 16],
+    /// The offset of the CARv1 data payload from the start of the CAR.
+    pub data_offset: u64,
+    /// The size of the data payload in bytes.
+    pub data_size: u64,
+    /// The offset of the index from the start of the CAR, it's zero if there is no index.
+    pub index_offset: u64,
+}
+
+impl CarV2Header {
+    /// Returns whether the index contains all blocks of the data payload, including the ones
+    /// with identity CIDs.
+    pub fn is_fully_indexed(&self) -> bool {
+        self.characteristics[0] & FULLY_INDEXED != 0
+    }
+
+    fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[..16].copy_from_slice(&self.characteristics);
+        bytes[16..24].copy_from_slice(&self.data_offset.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.data_size.to_le_bytes());
+        bytes[32..].copy_from_slice(&self.index_offset.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        let u64_at = |start: usize| u64::from_le_bytes(bytes[start..start + 8].try_into().unwrap());
+        Self {
+            characteristics: bytes[..16].try_into().unwrap(),
+            data_offset: u64_at(16),
+            data_size: u64_at(24),
+            index_offset: u64_at(32),
+        }
+    }
+
+    /// Checks that the data payload and the index don't overlap with the header or each other.
+    fn validate(&self) -> Result<(), CarError> {
+        let data_end = self.data_offset.checked_add(self.data_size);
+        if self.data_offset < DATA_OFFSET || data_end.is_none() {
+            return Err(CarError::InvalidHeader(format!(
+                "data payload at offset {} with size {} is out of bounds",
+                self.data_offset, self.data_size
+            )));
+        }
+        if self.index_offset != 0 && matches!(data_end, Some(end) if self.index_offset < end) {
+            return Err(CarError::InvalidHeader(format!(
+                "index at offset {} overlaps the data payload",
+                self.index_offset
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The format of the index of a CARv2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexCodec {
+    /// Index of the multihash digests, the hash function isn't recorded.
+    IndexSorted,
+    /// Index of the multihash digests, grouped by hash function.
+    MultihashIndexSorted,
+}
+
+impl IndexCodec {
+    /// Returns the multicodec code of the index format.
+    pub fn code(self) -> u64 {
+        match self {
+            Self::IndexSorted => 0x0400,
+            Self::MultihashIndexSorted => 0x0401,
+        }
+    }
+}
+
+impl TryFrom<u64> for IndexCodec {
+    type Error = CarError;
+
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            0x0400 => Ok(Self::IndexSorted),
+            0x0401 => Ok(Self::MultihashIndexSorted),
+            _ => Err(CarError::UnsupportedIndex(code)),
+        }
+    }
+}
+
+/// Index records whose digests have the same length, they are kept in their encoded form.
+#[derive(Debug)]
+struct Bucket {
+    /// The multihash code of the digests, it's `None` for [`IndexCodec::IndexSorted`].
+    code: Option<u64>,
+    /// The size of a record, the length of the digest plus eight bytes for the offset.
+    width: usize,
+    /// The records sorted by digest, each one is the digest followed by the offset of the
+    /// section relative to the data payload.
+    records: Vec<u8>,
+}
+
+impl Bucket {
+    fn len(&self) -> usize {
+        self.records.len() / self.width
+    }
+
+    fn digest(&self, index: usize) -> &[u8] {
+        &self.records[index * self.width..(index + 1) * self.width - 8]
+    }
+
+    fn offset(&self, index: usize) -> u64 {
+        let end = (index + 1) * self.width;
+        u64::from_le_bytes(self.records[end - 8..end].try_into().unwrap())
+    }
+
+    /// Returns the offsets of the records with the given digest, it's a binary search.
+    fn offsets<'a>(&'a self, digest: &'a [u8]) -> impl Iterator<Item = u64> + 'a {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.digest(middle) < digest {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        (low..self.len())
+            .take_while(move |index| self.digest(*index) == digest)
+            .map(move |index| self.offset(index))
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        output.extend((self.width as u32).to_le_bytes());
+        output.extend((self.records.len() as u64).to_le_bytes());
+        output.extend(&self.records);
+    }
+}
+
+/// An index of the sections of a CARv2 by the multihashes of their CIDs.
+#[derive(Debug)]
+struct Index {
+    codec: IndexCodec,
+    /// The buckets sorted by multihash code and width.
+    buckets: Vec<Bucket>,
+}
+
+impl Index {
+    /// Builds an index from the multihashes of the blocks and the offsets of their sections.
+    fn build(codec: IndexCodec, entries: &[(Multihash<64>, u64)]) -> Self {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (hash, offset) in entries {
+            let code = match codec {
+                IndexCodec::IndexSorted => None,
+                IndexCodec::MultihashIndexSorted => Some(hash.code()),
+            };
+            groups
+                .entry((code, hash.digest().len() + 8))
+                .or_default()
+                .push((hash.digest(), *offset));
+        }
+        let buckets = groups
+            .into_iter()
+            .map(|((code, width), mut records)| {
+                records.sort();
+                let mut bytes = Vec::with_capacity(records.len() * width);
+                for (digest, offset) in records {
+                    bytes.extend(digest);
+                    bytes.extend(offset.to_le_bytes());
+                }
+                Bucket {
+                    code,
+                    width,
+                    records: bytes,
+                }
+            })
+            .collect();
+        Self { codec, buckets }
+    }
+
+    /// Returns the offsets of the sections whose CIDs may have the given multihash.
+    fn offsets<'a>(&'a self, hash: &'a Multihash<64>) -> impl Iterator<Item = u64> + 'a {
+        let width = hash.digest().len() + 8;
+        self.buckets
+            .iter()
+            .filter(move |bucket| {
+                bucket.width == width && (bucket.code.is_none() || bucket.code == Some(hash.code()))
+            })
+            .flat_map(move |bucket| bucket.offsets(hash.digest()))
+    }
+
+    fn encode(&self, output: &mut Vec<u8>) {
+        write_varint(output, self.codec.code());
+        match self.codec {
+            IndexCodec::IndexSorted => encode_buckets(&self.buckets, output),
+            IndexCodec::MultihashIndexSorted => {
+                // The buckets are sorted by code, group the consecutive runs of equal codes.
+                let mut groups: Vec<&[Bucket]> = Vec::new();
+                let mut start = 0;
+                for end in 1..=self.buckets.len() {
+                    if end == self.buckets.len()
+                        || self.buckets[end].code != self.buckets[start].code
+                    {
+                        groups.push(&self.buckets[start..end]);
+                        start = end;
+                    }
+                }
+                output.extend((groups.len() as i32).to_le_bytes());
+                for buckets in groups {
+                    output.extend(buckets[0].code.unwrap_or_default().to_le_bytes());
+                    encode_buckets(buckets, output);
+                }
+            }
+        }
+    }
+
+    /// Decodes an index, `offset` is the position of the reader.
+    fn decode<R: BufRead>(reader: &mut R, offset: &mut u64) -> Result<Self, CarError> {
+        let start = *offset;
+        let code = read_varint(reader, offset)?.ok_or(CarError::UnexpectedEof { offset: start })?;
+        let codec = IndexCodec::try_from(code)?;
+        let mut buckets = Vec::new();
+        match codec {
+            IndexCodec::IndexSorted => decode_buckets(reader, offset, None, &mut buckets)?,
+            IndexCodec::MultihashIndexSorted => {
+                for _ in 0..read_count(reader, offset)? {
+                    let code = u64::from_le_bytes(read_array(reader, offset)?);
+                    if matches!(buckets.last(), Some(bucket) if bucket.code >= Some(code)) {
+                        return Err(CarError::InvalidIndex(format!(
+                            "multihash codes are not sorted at offset {}",
+                            *offset - 8
+                        )));
+                    }
+                    decode_buckets(reader, offset, Some(code), &mut buckets)?;
+                }
+            }
+        }
+        Ok(Self { codec, buckets })
+    }
+}
+
+fn encode_buckets(buckets: &[Bucket], output: &mut Vec<u8>) {
+    output.extend((buckets.len() as i32).to_le_bytes());
+    for bucket in buckets {
+        bucket.encode(output);
+    }
+}
+
+fn read_array<R: BufRead, const N: usize>(
+    reader: &mut R,
+    offset: &mut u64,
+) -> Result<[u8; N], CarError> {
+    Ok(read_exact(reader, N, offset)?
+        .try_into()
+        .expect("read_exact returns the requested number of bytes"))
+}
+
+/// Reads the number of items that follow, it's a signed 32-bit integer.
+fn read_count<R: BufRead>(reader: &mut R, offset: &mut u64) -> Result<u32, CarError> {
+    let count = i32::from_le_bytes(read_array(reader, offset)?);
+    u32::try_from(count)
+        .map_err(|_| CarError::InvalidIndex(format!("negative count at offset {}", *offset - 4)))
+}
+
+/// Decodes the buckets of an `IndexSorted` index and appends them to `buckets`.
+fn decode_buckets<R: BufRead>(
+    reader: &mut R,
+    offset: &mut u64,
+    code: Option<u64>,
+    buckets: &mut Vec<Bucket>,
+) -> Result<(), CarError> {
+    let mut previous_width = 0;
+    for _ in 0..read_count(reader, offset)? {
+        let start = *offset;
+        let width = u32::from_le_bytes(read_array(reader, offset)?) as usize;
+        let len = u64::from_le_bytes(read_array(reader, offset)?);
+        if width <= 8 || width <= previous_width {
+            return Err(CarError::InvalidIndex(format!(
+                "invalid record width {} at offset {}",
+                width, start
+            )));
+        }
+        previous_width = width;
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|len| len % width == 0)
+            .ok_or_else(|| {
+                CarError::InvalidIndex(format!("invalid bucket size {} at offset {}", len, start))
+            })?;
+        let bucket = Bucket {
+            code,
+            width,
+            records: read_exact(reader, len, offset)?,
+        };
+        if (1..bucket.len()).any(|index| bucket.digest(index - 1) > bucket.digest(index)) {
+            return Err(CarError::InvalidIndex(format!(
+                "records are not sorted in the bucket at offset {}",
+                start
+            )));
+        }
+        buckets.push(bucket);
+    }
+    Ok(())
+}
+
+/// A reader of a CAR with random access to its blocks.
+///
+/// Blocks are looked up by CID with a binary search in the index, only the index is kept in
+/// memory. If a CARv2 has no index, or a CARv1 is read, the index is generated by reading all
+/// blocks once.
+///
+/// # Example
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use ipld_core::{car::{CarV2Reader, CarV2Writer}, cid::Cid};
+///
+/// let cid = Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+/// let mut writer = CarV2Writer::new(Cursor::new(Vec::new()), vec![cid]).unwrap();
+/// writer.write(&cid, b"data").unwrap();
+/// let car = writer.finish().unwrap();
+///
+/// let mut reader = CarV2Reader::new(car).unwrap();
+/// assert_eq!(reader.header().roots, [cid]);
+/// assert_eq!(reader.get(&cid).unwrap(), Some(b"data".to_vec()));
+/// ```
+#[derive(Debug)]
+pub struct CarV2Reader<R> {
+    reader: R,
+    header: CarHeader,
+    v2_header: Option<CarV2Header>,
+    data_offset: u64,
+    /// The size of the data payload, it's `None` for a CARv1 which extends to the end.
+    data_size: Option<u64>,
+    index: Index,
+    max_section_size: usize,
+}
+
+impl<R: BufRead + Seek> CarV2Reader<R> {
+    /// Creates a reader of the CAR that starts at the beginning of the given reader.
+    ///
+    /// Both CARv2 and CARv1 are supported.
+    pub fn new(mut reader: R) -> Result<Self, CarError> {
+        reader.rewind()?;
+        let mut offset = 0;
+        let bytes = v1::read_header_bytes(&mut reader, &mut offset)?;
+        let (header, v2_header, index) = match decode_version(&bytes)? {
+            1 => (CarHeader::decode(&bytes)?, None, None),
+            2 => {
+                let v2_header =
+                    CarV2Header::decode(&read_exact(&mut reader, HEADER_LEN, &mut offset)?);
+                v2_header.validate()?;
+                reader.seek(SeekFrom::Start(v2_header.data_offset))?;
+                let mut offset = v2_header.data_offset;
+                let header = v1::read_header(&mut reader, &mut offset)?;
+                let index = match v2_header.index_offset {
+                    0 => None,
+                    mut index_offset => {
+                        reader.seek(SeekFrom::Start(index_offset))?;
+                        Some(Index::decode(&mut reader, &mut index_offset)?)
+                    }
+                };
+                (header, Some(v2_header), index)
+            }
+            version => return Err(CarError::UnsupportedVersion(version)),
+        };
+        let mut car = Self {
+            reader,
+            header,
+            v2_header,
+            data_offset: v2_header.map_or(0, |header| header.data_offset),
+            data_size: v2_header.map(|header| header.data_size),
+            index: Index {
+                codec: IndexCodec::MultihashIndexSorted,
+                buckets: Vec::new(),
+            },
+            max_section_size: DEFAULT_MAX_SECTION_SIZE,
+        };
+        car.index = match index {
+            Some(index) => index,
+            None => car.generate_index()?,
+        };
+        Ok(car)
+    }
+
+    /// Generates an index by reading all blocks of the data payload.
+    fn generate_index(&mut self) -> Result<Index, CarError> {
+        self.reader.seek(SeekFrom::Start(self.data_offset))?;
+        let mut payload = io::Read::take(&mut self.reader, self.data_size.unwrap_or(u64::MAX));
+        let mut offset = self.data_offset;
+        v1::read_header(&mut payload, &mut offset)?;
+        let mut entries = Vec::new();
+        loop {
+            let start = offset - self.data_offset;
+            match v1::read_section(&mut payload, &mut offset, self.max_section_size)? {
+                Some((cid, _)) => entries.push((*cid.hash(), start)),
+                None => break,
+            }
+        }
+        Ok(Index::build(IndexCodec::MultihashIndexSorted, &entries))
+    }
+
+    /// Sets the maximum size of a section, the default is [`DEFAULT_MAX_SECTION_SIZE`].
+    pub fn max_section_size(mut self, max: usize) -> Self {
+        self.max_section_size = max;
+        self
+    }
+
+    /// Returns the header of the data payload.
+    pub fn header(&self) -> &CarHeader {
+        &self.header
+    }
+
+    /// Returns the CARv2 header, it's `None` for a CARv1.
+    pub fn v2_header(&self) -> Option<&CarV2Header> {
+        self.v2_header.as_ref()
+    }
+
+    /// Returns the data of the block with the given CID, or `None` if the CAR doesn't contain it.
+    pub fn get(&mut self, cid: &Cid) -> Result<Option<Vec<u8>>, CarError> {
+        let offsets: Vec<_> = self.index.offsets(cid.hash()).collect();
+        for offset in offsets {
+            let mut offset = self.data_offset.checked_add(offset).ok_or_else(|| {
+                CarError::InvalidIndex(format!("section offset {} is out of bounds", offset))
+            })?;
+            self.reader.seek(SeekFrom::Start(offset))?;
+            // Sections of CIDs with the same multihash, but a different codec, share an entry.
+            match v1::read_section(&mut self.reader, &mut offset, self.max_section_size)? {
+                Some((found, data)) if found == *cid => return Ok(Some(data)),
+                Some(_) => {}
+                None => return Err(CarError::UnexpectedEof { offset }),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns a reader that iterates over all blocks of the data payload.
+    ///
+    /// The offsets in its errors are relative to the data payload.
+    pub fn blocks(&mut self) -> Result<CarReader<io::Take<&mut R>>, CarError> {
+        self.reader.seek(SeekFrom::Start(self.data_offset))?;
+        let payload = io::Read::take(&mut self.reader, self.data_size.unwrap_or(u64::MAX));
+        Ok(CarReader::new(payload)?.max_section_size(self.max_section_size))
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// A writer of a CARv2.
+///
+/// The data payload directly follows the header, the index is written after it when the writer
+/// is finished. As the header contains the size of the data payload, it's written last.
+#[derive(Debug)]
+pub struct CarV2Writer<W: Write + Seek> {
+    writer: W,
+    /// The position of the start of the CAR in the writer.
+    start: u64,
+    data_size: u64,
+    /// The multihashes of the written blocks and the offsets of their sections.
+    entries: Vec<(Multihash<64>, u64)>,
+    index_codec: Option<IndexCodec>,
+}
+
+impl<W: Write + Seek> CarV2Writer<W> {
+    /// Creates a writer that writes a CAR with the given roots at the current position.
+    ///
+    /// An index in the [`IndexCodec::MultihashIndexSorted`] format is written by default.
+    pub fn new(mut writer: W, roots: Vec<Cid>) -> Result<Self, CarError> {
+        let start = writer.stream_position()?;
+        writer.write_all(&PRAGMA)?;
+        writer.write_all(&[0; HEADER_LEN])?;
+        let header = v1::encode_header(&CarHeader::new(roots))?;
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            start,
+            data_size: header.len() as u64,
+            entries: Vec::new(),
+            index_codec: Some(IndexCodec::MultihashIndexSorted),
+        })
+    }
+
+    /// Sets the format of the index, no index is written if it's `None`.
+    pub fn index_codec(mut self, codec: Option<IndexCodec>) -> Self {
+        self.index_codec = codec;
+        self
+    }
+
+    /// Writes a block.
+    ///
+    /// The data isn't verified against the CID.
+    pub fn write(&mut self, cid: &Cid, data: &[u8]) -> Result<(), CarError> {
+        let size = v1::write_section(&mut self.writer, cid, data)?;
+        self.entries.push((*cid.hash(), self.data_size));
+        self.data_size += size;
+        Ok(())
+    }
+
+    /// Writes the index and the header, flushes the writer and returns it.
+    ///
+    /// The writer is positioned at the end of the CAR.
+    pub fn finish(mut self) -> Result<W, CarError> {
+        let mut header = CarV2Header {
+            characteristics: [0; 16],
+            data_offset: DATA_OFFSET,
+            data_size: self.data_size,
+            index_offset: 0,
+        };
+        let mut end = DATA_OFFSET + self.data_size;
+        if let Some(codec) = self.index_codec {
+            let mut index = Vec::new();
+            Index::build(codec, &self.entries).encode(&mut index);
+            self.writer.write_all(&index)?;
+            // Every block is indexed, including the ones with identity CIDs.
+            header.characteristics[0] |= FULLY_INDEXED;
+            header.index_offset = end;
+            end += index.len() as u64;
+        }
+        self.writer
+            .seek(SeekFrom::Start(self.start + PRAGMA.len() as u64))?;
+        self.writer.write_all(&header.encode())?;
+        self.writer.seek(SeekFrom::Start(self.start + end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
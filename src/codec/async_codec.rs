@@ -0,0 +1,241 @@
+//! Encoding and decoding with asynchronous readers and writers.
+use std::{fmt, future::Future, io, pin::Pin};
+
+use futures_io::{AsyncRead, AsyncWrite};
+
+use crate::codec::Codec;
+
+/// The size of the chunks the data is read in.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// The default maximum size of the data [`AsyncCodec::decode_async`] reads, it's 32 MiB.
+pub const DEFAULT_MAX_DECODE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Error when encoding or decoding with an [`AsyncCodec`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AsyncCodecError<E> {
+    /// Error when reading or writing failed.
+    Io(io::Error),
+    /// Error returned by the codec.
+    Codec(E),
+    /// Error when the data to decode is larger than the maximum size.
+    TooLarge(usize),
+}
+
+impl<E: fmt::Display> fmt::Display for AsyncCodecError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            Self::Codec(error) => write!(f, "codec error: {}", error),
+            Self::TooLarge(max) => write!(f, "data exceeds the maximum size of {} bytes", max),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for AsyncCodecError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Codec(error) => Some(error),
+            Self::TooLarge(_) => None,
+        }
+    }
+}
+
+impl<E> From<io::Error> for AsyncCodecError<E> {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A [`Codec`] that encodes into and decodes from asynchronous readers and writers.
+///
+/// The traits of `futures-io` are used, so that it's independent of the async runtime. The
+/// default implementations buffer the whole data in memory and use the synchronous codec, so an
+/// empty `impl` is enough. Codecs that support streaming can override them.
+///
+/// The returned futures are `Send`, so that they can be spawned on multi-threaded executors.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{codec::{AsyncCodec, RawCodec}, ipld::Ipld};
+///
+/// futures_executor::block_on(async {
+///     let mut bytes = Vec::new();
+///     RawCodec::encode_async(&mut bytes, &Ipld::Bytes(vec![1, 2, 3])).await.unwrap();
+///     let ipld: Ipld = RawCodec::decode_async(&bytes[..]).await.unwrap();
+///     assert_eq!(ipld, Ipld::Bytes(vec![1, 2, 3]));
+/// });
+/// ```
+pub trait AsyncCodec<T>: Codec<T> {
+    /// The maximum size of the data [`AsyncCodec::decode_async`] reads, the default is
+    /// [`DEFAULT_MAX_DECODE_SIZE`].
+    const MAX_DECODE_SIZE: usize = DEFAULT_MAX_DECODE_SIZE;
+
+    /// Decode an asynchronous reader into the desired type.
+    ///
+    /// The reader is read until its end. If it returns more than [`AsyncCodec::MAX_DECODE_SIZE`]
+    /// bytes, [`AsyncCodecError::TooLarge`] is returned.
+    fn decode_async<R>(
+        reader: R,
+    ) -> impl Future<Output = Result<T, AsyncCodecError<Self::Error>>> + Send
+    where
+        R: AsyncRead + Unpin + Send,
+        T: Send,
+        Self::Error: Send,
+    {
+        async move {
+            let bytes = read_to_end(reader, Self::MAX_DECODE_SIZE).await?;
+            Self::decode_from_slice(&bytes).map_err(AsyncCodecError::Codec)
+        }
+    }
+
+    /// Encode a type into an asynchronous writer, the writer is flushed afterwards.
+    fn encode_async<W>(
+        writer: W,
+        data: &T,
+    ) -> impl Future<Output = Result<(), AsyncCodecError<Self::Error>>> + Send
+    where
+        W: AsyncWrite + Unpin + Send,
+        T: Sync,
+        Self::Error: Send,
+    {
+        let bytes = Self::encode_to_vec(data);
+        async move {
+            let bytes = bytes.map_err(AsyncCodecError::Codec)?;
+            write_all(writer, &bytes).await?;
+            Ok(())
+        }
+    }
+}
+
+/// Reads all data until the end of the reader, but at most `max_size` bytes.
+async fn read_to_end<R: AsyncRead + Unpin, E>(
+    mut reader: R,
+    max_size: usize,
+) -> Result<Vec<u8>, AsyncCodecError<E>> {
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        match std::future::poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut chunk)).await {
+            Ok(0) => return Ok(bytes),
+            Ok(read) if read > max_size - bytes.len() => {
+                return Err(AsyncCodecError::TooLarge(max_size))
+            }
+            Ok(read) => bytes.extend_from_slice(&chunk[..read]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+/// Writes all data and flushes the writer.
+async fn write_all<W: AsyncWrite + Unpin>(mut writer: W, mut bytes: &[u8]) -> io::Result<()> {
+    while !bytes.is_empty() {
+        match std::future::poll_fn(|cx| Pin::new(&mut writer).poll_write(cx, bytes)).await {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => bytes = &bytes[written..],
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    std::future::poll_fn(|cx| Pin::new(&mut writer).poll_flush(cx)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::{Context, Poll};
+
+    use futures_executor::block_on;
+
+    use super::*;
+    use crate::{
+        codec::{RawCodec, RawCodecError},
+        ipld::Ipld,
+    };
+
+    /// A reader that returns a single byte per read and is interrupted in between.
+    struct SlowReader {
+        data: Vec<u8>,
+        interrupt: bool,
+    }
+
+    impl AsyncRead for SlowReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Poll::Ready(Err(io::ErrorKind::Interrupted.into()));
+            }
+            if self.data.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            buf[0] = self.data.remove(0);
+            Poll::Ready(Ok(1))
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        block_on(async {
+            let data = vec![1, 2, 3];
+            let mut bytes = Vec::new();
+            RawCodec::encode_async(&mut bytes, &data).await.unwrap();
+            assert_eq!(bytes, data);
+
+            let reader = SlowReader {
+                data: data.clone(),
+                interrupt: false,
+            };
+            let decoded: Ipld = RawCodec::decode_async(reader).await.unwrap();
+            assert_eq!(decoded, Ipld::Bytes(data));
+        });
+    }
+
+    #[test]
+    fn test_max_decode_size() {
+        block_on(async {
+            let bytes = read_to_end::<_, RawCodecError>(&[1, 2, 3, 4][..], 4).await;
+            assert_eq!(bytes.unwrap(), [1, 2, 3, 4]);
+            assert!(matches!(
+                read_to_end::<_, RawCodecError>(&[1, 2, 3, 4, 5][..], 4).await,
+                Err(AsyncCodecError::TooLarge(4))
+            ));
+        });
+    }
+
+    /// Spawning on a multi-threaded executor requires `Send` futures.
+    #[test]
+    fn test_futures_are_send() {
+        fn assert_send<F: Future + Send>(future: F) -> F {
+            future
+        }
+
+        block_on(async {
+            let mut bytes = Vec::new();
+            assert_send(RawCodec::encode_async(&mut bytes, &Ipld::Bytes(vec![1])))
+                .await
+                .unwrap();
+            let decoded: Ipld = assert_send(RawCodec::decode_async(&bytes[..]))
+                .await
+                .unwrap();
+            assert_eq!(decoded, Ipld::Bytes(vec![1]));
+        });
+    }
+
+    #[test]
+    fn test_codec_error() {
+        block_on(async {
+            let result = RawCodec::encode_async(Vec::new(), &Ipld::Null).await;
+            assert!(matches!(
+                result,
+                Err(AsyncCodecError::Codec(RawCodecError::NotBytes(_)))
+            ));
+        });
+    }
+}
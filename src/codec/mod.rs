@@ -0,0 +1,97 @@
+//! This module contains traits to have a unified API across codecs.
+//!
+//! There are two traits defined, [`Codec`] and [`Links`]. Those are separate traits as the `Links`
+//! trait is not generic over a certain type.
+//!
+//! [`Codec`] works with readers and writers and therefore needs the `std` feature. Codecs that
+//! should also work without `std` implement [`SliceCodec`] as well, which works with byte slices
+//! and an output buffer.
+//!
+//! The [`Codec`] trait is static, the codec needs to be known at compile time. [`DynCodec`] is an
+//! object safe counterpart for [`Ipld`], a [`CodecRegistry`] uses it to pick the codec of a block
+//! at runtime, based on the codec code of its CID.
+//!
+//! With the `async` feature enabled, [`AsyncCodec`] encodes into and decodes from asynchronous
+//! readers and writers.
+//!
+//! The [`RawCodec`] is built in, it's the codec for blocks of plain bytes.
+//!
+//! [`Ipld`]: crate::ipld::Ipld
+#[cfg(feature = "async")]
+mod async_codec;
+mod raw;
+#[cfg(feature = "std")]
+mod registry;
+
+use alloc::vec::Vec;
+
+use cid::Cid;
+
+#[cfg(feature = "std")]
+use std::io::{BufRead, Write};
+
+#[cfg(feature = "async")]
+pub use async_codec::{AsyncCodec, AsyncCodecError, DEFAULT_MAX_DECODE_SIZE};
+pub use raw::{RawCodec, RawCodecError};
+#[cfg(feature = "std")]
+pub use registry::{CodecRegistry, CodecRegistryError, DynCodec, DynCodecError};
+
+/// Returns the data of a block whose CID uses the identity hash function.
+///
+/// Such a CID contains the data of the block inline, the data doesn't need to be loaded. `None`
+/// is returned for all other hash functions.
+pub fn inline_data(cid: &Cid) -> Option<&[u8]> {
+    (cid.hash().code() == crate::IDENTITY_HASH_CODE).then(|| cid.hash().digest())
+}
+
+/// Each IPLD codec implementation should implement this Codec trait. This way codecs can be more
+/// easily exchanged or combined.
+#[cfg(feature = "std")]
+pub trait Codec<T>: Links {
+    /// The multicodec code of the IPLD codec.
+    const CODE: u64;
+    /// The error that is returned if encoding or decoding fails.
+    type Error;
+
+    /// Decode a reader into the desired type.
+    fn decode<R: BufRead>(reader: R) -> Result<T, Self::Error>;
+    /// Encode a type into a writer.
+    fn encode<W: Write>(writer: W, data: &T) -> Result<(), Self::Error>;
+
+    /// Decode a slice into the desired type.
+    fn decode_from_slice(bytes: &[u8]) -> Result<T, Self::Error> {
+        Self::decode(bytes)
+    }
+
+    /// Encode a type into bytes.
+    fn encode_to_vec(data: &T) -> Result<Vec<u8>, Self::Error> {
+        let mut output = Vec::new();
+        Self::encode(&mut output, data)?;
+        Ok(output)
+    }
+}
+
+/// A codec that works on byte slices, it's available without the `std` feature.
+///
+/// A codec that supports both targets implements `SliceCodec` and, with the `std` feature
+/// enabled, [`Codec`] by delegating to it, see [`RawCodec`] for an example.
+pub trait SliceCodec<T>: Links {
+    /// The multicodec code of the IPLD codec.
+    const CODE: u64;
+    /// The error that is returned if encoding or decoding fails.
+    type Error;
+
+    /// Decode a slice into the desired type.
+    fn decode_slice(bytes: &[u8]) -> Result<T, Self::Error>;
+    /// Encode a type by appending it to the output buffer.
+    fn encode_into(data: &T, output: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
+
+/// Trait for returning the links of a serialized IPLD data.
+pub trait Links {
+    /// The error that is returned if the link extraction fails.
+    type LinksError;
+
+    /// Return all links (CIDs) that the given encoded data contains.
+    fn links(bytes: &[u8]) -> Result<impl Iterator<Item = Cid>, Self::LinksError>;
+}
@@ -0,0 +1,207 @@
+//! The raw codec, the data of a block is plain bytes.
+use alloc::vec::Vec;
+use core::{convert::Infallible, fmt};
+
+use cid::Cid;
+
+#[cfg(feature = "std")]
+use std::io::{BufRead, Write};
+
+#[cfg(feature = "std")]
+use crate::codec::Codec;
+use crate::{
+    codec::{Links, SliceCodec},
+    ipld::{Ipld, IpldKind},
+};
+
+/// The codec for blocks that are plain bytes, without any structure.
+///
+/// As [`Ipld`] it's decoded into [`Ipld::Bytes`], only those can be encoded. Raw blocks never
+/// contain links.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{codec::{Codec, RawCodec}, ipld::Ipld};
+///
+/// let bytes = RawCodec::encode_to_vec(&Ipld::Bytes(vec![1, 2, 3])).unwrap();
+/// assert_eq!(bytes, [1, 2, 3]);
+/// let ipld: Ipld = RawCodec::decode_from_slice(&bytes).unwrap();
+/// assert_eq!(ipld, Ipld::Bytes(vec![1, 2, 3]));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RawCodec;
+
+/// Error when encoding or decoding with the [`RawCodec`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RawCodecError {
+    /// Error when reading or writing failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Error when IPLD that isn't bytes is encoded.
+    NotBytes(IpldKind),
+}
+
+impl fmt::Display for RawCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(error) => write!(f, "I/O error: {}", error),
+            Self::NotBytes(kind) => write!(f, "expected bytes, found {:?}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RawCodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::NotBytes(_) => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for RawCodecError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl RawCodec {
+    /// The multicodec code of the raw codec.
+    pub const CODE: u64 = 0x55;
+}
+
+impl SliceCodec<Vec<u8>> for RawCodec {
+    const CODE: u64 = Self::CODE;
+    type Error = RawCodecError;
+
+    fn decode_slice(bytes: &[u8]) -> Result<Vec<u8>, Self::Error> {
+        Ok(bytes.to_vec())
+    }
+
+    fn encode_into(data: &Vec<u8>, output: &mut Vec<u8>) -> Result<(), Self::Error> {
+        output.extend_from_slice(data);
+        Ok(())
+    }
+}
+
+impl SliceCodec<Ipld> for RawCodec {
+    const CODE: u64 = Self::CODE;
+    type Error = RawCodecError;
+
+    fn decode_slice(bytes: &[u8]) -> Result<Ipld, Self::Error> {
+        Ok(Ipld::Bytes(bytes.to_vec()))
+    }
+
+    fn encode_into(data: &Ipld, output: &mut Vec<u8>) -> Result<(), Self::Error> {
+        match data {
+            Ipld::Bytes(bytes) => {
+                output.extend_from_slice(bytes);
+                Ok(())
+            }
+            other => Err(RawCodecError::NotBytes(other.kind())),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Codec<T> for RawCodec
+where
+    Self: SliceCodec<T, Error = RawCodecError>,
+{
+    const CODE: u64 = Self::CODE;
+    type Error = RawCodecError;
+
+    fn decode<R: BufRead>(mut reader: R) -> Result<T, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::decode_slice(&bytes)
+    }
+
+    fn encode<W: Write>(mut writer: W, data: &T) -> Result<(), Self::Error> {
+        Ok(writer.write_all(&Self::encode_to_vec(data)?)?)
+    }
+
+    fn decode_from_slice(bytes: &[u8]) -> Result<T, Self::Error> {
+        Self::decode_slice(bytes)
+    }
+
+    fn encode_to_vec(data: &T) -> Result<Vec<u8>, Self::Error> {
+        let mut output = Vec::new();
+        Self::encode_into(data, &mut output)?;
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> crate::codec::AsyncCodec<T> for RawCodec where Self: Codec<T> {}
+
+impl Links for RawCodec {
+    type LinksError = Infallible;
+
+    fn links(_bytes: &[u8]) -> Result<impl Iterator<Item = Cid>, Self::LinksError> {
+        Ok(core::iter::empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let data = vec![0, 1, 2, 0xff];
+        let mut bytes = Vec::new();
+        RawCodec::encode_into(&data, &mut bytes).unwrap();
+        assert_eq!(bytes, data);
+        assert_eq!(
+            <RawCodec as SliceCodec<Vec<u8>>>::decode_slice(&bytes).unwrap(),
+            data
+        );
+
+        let ipld = Ipld::Bytes(data.clone());
+        let mut bytes = Vec::new();
+        RawCodec::encode_into(&ipld, &mut bytes).unwrap();
+        assert_eq!(bytes, data);
+        assert_eq!(
+            <RawCodec as SliceCodec<Ipld>>::decode_slice(&bytes).unwrap(),
+            ipld
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_codec() {
+        let data = vec![0, 1, 2, 0xff];
+        let bytes = RawCodec::encode_to_vec(&data).unwrap();
+        assert_eq!(bytes, data);
+        assert_eq!(
+            <RawCodec as Codec<Vec<u8>>>::decode(&bytes[..]).unwrap(),
+            data
+        );
+        let ipld = Ipld::Bytes(data.clone());
+        let mut output = Vec::new();
+        RawCodec::encode(&mut output, &ipld).unwrap();
+        assert_eq!(output, data);
+        assert_eq!(<RawCodec as Codec<Ipld>>::decode(&bytes[..]).unwrap(), ipld);
+    }
+
+    #[test]
+    fn test_not_bytes() {
+        assert!(matches!(
+            RawCodec::encode_into(&Ipld::String("bytes".to_string()), &mut Vec::new()),
+            Err(RawCodecError::NotBytes(IpldKind::String))
+        ));
+    }
+
+    #[test]
+    fn test_no_links() {
+        assert_eq!(RawCodec::links(b"bafkqaaa").unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,189 @@
+//! Runtime dispatch of codecs by their multicodec code.
+use std::{collections::BTreeMap, error::Error, fmt, marker::PhantomData};
+
+use cid::Cid;
+
+use crate::{
+    codec::{inline_data, Codec},
+    ipld::Ipld,
+};
+
+/// A boxed error returned by a [`DynCodec`].
+pub type DynCodecError = Box<dyn Error + Send + Sync>;
+
+/// An object safe codec that encodes and decodes [`Ipld`].
+///
+/// Every [`Codec`] for [`Ipld`] can be used as a `DynCodec` through [`CodecRegistry::with_codec`].
+pub trait DynCodec: Send + Sync {
+    /// The multicodec code of the IPLD codec.
+    fn code(&self) -> u64;
+    /// Decode a slice into IPLD.
+    fn decode(&self, bytes: &[u8]) -> Result<Ipld, DynCodecError>;
+    /// Encode IPLD into bytes.
+    fn encode(&self, ipld: &Ipld) -> Result<Vec<u8>, DynCodecError>;
+    /// Return all links (CIDs) that the given encoded data contains.
+    fn links(&self, bytes: &[u8]) -> Result<Vec<Cid>, DynCodecError>;
+}
+
+/// Wraps a static [`Codec`] so that it can be used as a [`DynCodec`].
+struct StaticCodec<C>(PhantomData<fn() -> C>);
+
+impl<C> DynCodec for StaticCodec<C>
+where
+    C: Codec<Ipld>,
+    C::Error: Error + Send + Sync + 'static,
+    C::LinksError: Error + Send + Sync + 'static,
+{
+    fn code(&self) -> u64 {
+        C::CODE
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Ipld, DynCodecError> {
+        Ok(C::decode_from_slice(bytes)?)
+    }
+
+    fn encode(&self, ipld: &Ipld) -> Result<Vec<u8>, DynCodecError> {
+        Ok(C::encode_to_vec(ipld)?)
+    }
+
+    fn links(&self, bytes: &[u8]) -> Result<Vec<Cid>, DynCodecError> {
+        Ok(C::links(bytes)?.collect())
+    }
+}
+
+/// Error when encoding, decoding or extracting links through a [`CodecRegistry`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CodecRegistryError {
+    /// Error when no codec is registered for a codec code.
+    UnknownCodec(u64),
+    /// Error returned by a codec.
+    Codec {
+        /// The codec code of the codec.
+        code: u64,
+        /// The error returned by the codec.
+        source: DynCodecError,
+    },
+}
+
+impl fmt::Display for CodecRegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownCodec(code) => write!(f, "no codec for code {:#x}", code),
+            Self::Codec { code, source } => write!(f, "codec {:#x} failed: {}", code, source),
+        }
+    }
+}
+
+impl Error for CodecRegistryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Codec { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+/// A set of codecs, keyed by their multicodec code.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{cid::{multihash::Multihash, Cid}, codec::CodecRegistry, ipld};
+/// use serde_ipld_dagcbor::codec::DagCborCodec;
+///
+/// let registry = CodecRegistry::new().with_codec::<DagCborCodec>();
+/// let bytes = registry.encode(0x71, &ipld!({"hello": "world"})).unwrap();
+/// // The CID is made up, the hash isn't verified when decoding.
+/// let cid = Cid::new_v1(0x71, Multihash::wrap(0x12, &[0; 32]).unwrap());
+/// assert_eq!(registry.decode_block(&cid, &bytes).unwrap(), ipld!({"hello": "world"}));
+/// ```
+#[derive(Default)]
+pub struct CodecRegistry {
+    codecs: BTreeMap<u64, Box<dyn DynCodec>>,
+}
+
+impl CodecRegistry {
+    /// Creates a registry without any codecs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a static codec, it replaces a codec with the same code.
+    pub fn with_codec<C>(mut self) -> Self
+    where
+        C: Codec<Ipld> + 'static,
+        C::Error: Error + Send + Sync + 'static,
+        C::LinksError: Error + Send + Sync + 'static,
+    {
+        self.register(Box::new(StaticCodec::<C>(PhantomData)));
+        self
+    }
+
+    /// Registers a codec and returns the one it replaces, if there was one with the same code.
+    pub fn register(&mut self, codec: Box<dyn DynCodec>) -> Option<Box<dyn DynCodec>> {
+        self.codecs.insert(codec.code(), codec)
+    }
+
+    /// Returns the codec with the given code.
+    pub fn get(&self, code: u64) -> Option<&dyn DynCodec> {
+        self.codecs.get(&code).map(|codec| &**codec)
+    }
+
+    /// Returns whether there is a codec with the given code.
+    pub fn contains(&self, code: u64) -> bool {
+        self.codecs.contains_key(&code)
+    }
+
+    /// Returns the codes of all codecs in ascending order.
+    pub fn codes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.codecs.keys().copied()
+    }
+
+    fn codec(&self, code: u64) -> Result<&dyn DynCodec, CodecRegistryError> {
+        self.get(code).ok_or(CodecRegistryError::UnknownCodec(code))
+    }
+
+    /// Decodes a block with the codec that matches the codec code of its CID.
+    ///
+    /// The data isn't verified against the hash of the CID. If the CID uses the identity hash
+    /// function, the data inlined into the CID is decoded and `bytes` is ignored, see
+    /// [`inline_data`].
+    pub fn decode_block(&self, cid: &Cid, bytes: &[u8]) -> Result<Ipld, CodecRegistryError> {
+        self.decode(cid.codec(), inline_data(cid).unwrap_or(bytes))
+    }
+
+    /// Decodes data with the codec with the given code.
+    pub fn decode(&self, code: u64, bytes: &[u8]) -> Result<Ipld, CodecRegistryError> {
+        self.codec(code)?
+            .decode(bytes)
+            .map_err(|source| CodecRegistryError::Codec { code, source })
+    }
+
+    /// Encodes IPLD with the codec with the given code.
+    pub fn encode(&self, code: u64, ipld: &Ipld) -> Result<Vec<u8>, CodecRegistryError> {
+        self.codec(code)?
+            .encode(ipld)
+            .map_err(|source| CodecRegistryError::Codec { code, source })
+    }
+
+    /// Returns the links of a block, extracted with the codec that matches the codec code of its
+    /// CID.
+    ///
+    /// Like [`CodecRegistry::decode_block`], the data inlined into identity CIDs is used instead of
+    /// `bytes`.
+    pub fn links(&self, cid: &Cid, bytes: &[u8]) -> Result<Vec<Cid>, CodecRegistryError> {
+        let code = cid.codec();
+        self.codec(code)?
+            .links(inline_data(cid).unwrap_or(bytes))
+            .map_err(|source| CodecRegistryError::Codec { code, source })
+    }
+}
+
+impl fmt::Debug for CodecRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CodecRegistry")
+            .field("codes", &self.codecs.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
@@ -57,16 +57,23 @@ impl fmt::Display for ConversionError {
 #[cfg(feature = "std")]
 impl std::error::Error for ConversionError {}
 
+/// Returns the error for a value of the wrong kind, the value is dropped without recursing.
+fn wrong_kind(expected: IpldKind, found: Ipld) -> ConversionError {
+    let error = ConversionError::WrongIpldKind {
+        expected,
+        found: found.kind(),
+    };
+    found.drop_iterative();
+    error
+}
+
 impl TryFrom<Ipld> for () {
     type Error = ConversionError;
 
     fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
         match ipld {
             Ipld::Null => Ok(()),
-            _ => Err(ConversionError::WrongIpldKind {
-                expected: IpldKind::Null,
-                found: ipld.kind(),
-            }),
+            _ => Err(wrong_kind(IpldKind::Null, ipld)),
         }
     }
 }
@@ -85,10 +92,7 @@ macro_rules! derive_try_from_ipld_option {
                             into: TypeId::of::<$ty>(),
                         }
                     })?)),
-                    _ => Err(ConversionError::WrongIpldKind {
-                        expected: IpldKind::$enum,
-                        found: ipld.kind(),
-                    }),
+                    _ => Err(wrong_kind(IpldKind::$enum, ipld)),
                 }
             }
         }
@@ -109,10 +113,7 @@ macro_rules! derive_try_from_ipld {
                         })?)
                     }
 
-                    _ => Err(ConversionError::WrongIpldKind {
-                        expected: IpldKind::$enum,
-                        found: ipld.kind(),
-                    }),
+                    _ => Err(wrong_kind(IpldKind::$enum, ipld)),
                 }
             }
         }
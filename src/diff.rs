@@ -0,0 +1,309 @@
+//! Structural diff between IPLD values.
+//!
+//! [`diff`] compares two values and reports every difference together with the path where it
+//! occurred. The changes can be converted into [`PatchOp`]s, applying those to the old value
+//! results in the new one.
+use alloc::{vec, vec::Vec};
+use core::mem;
+
+use cid::Cid;
+
+use crate::{ipld::Ipld, patch::PatchOp, path::IpldPath};
+
+/// A single difference between two IPLD values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A List element or Map entry only exists in the new value.
+    Added {
+        /// The path of the added value.
+        path: IpldPath,
+        /// The added value.
+        value: Ipld,
+    },
+    /// A List element or Map entry only exists in the old value.
+    Removed {
+        /// The path of the removed value.
+        path: IpldPath,
+        /// The removed value.
+        value: Ipld,
+    },
+    /// The value changed to a different kind.
+    KindChanged {
+        /// The path of the changed value.
+        path: IpldPath,
+        /// The old value.
+        old: Ipld,
+        /// The new value.
+        new: Ipld,
+    },
+    /// The value changed, but it's still of the same kind.
+    ValueChanged {
+        /// The path of the changed value.
+        path: IpldPath,
+        /// The old value.
+        old: Ipld,
+        /// The new value.
+        new: Ipld,
+    },
+    /// A link points to a different CID.
+    LinkChanged {
+        /// The path of the changed link.
+        path: IpldPath,
+        /// The old CID.
+        old: Cid,
+        /// The new CID.
+        new: Cid,
+    },
+}
+
+impl Change {
+    /// Returns the path where the change occurred.
+    pub fn path(&self) -> &IpldPath {
+        match self {
+            Self::Added { path, .. }
+            | Self::Removed { path, .. }
+            | Self::KindChanged { path, .. }
+            | Self::ValueChanged { path, .. }
+            | Self::LinkChanged { path, .. } => path,
+        }
+    }
+}
+
+impl From<Change> for PatchOp {
+    fn from(change: Change) -> Self {
+        match change {
+            Change::Added { path, value } => PatchOp::Add { path, value },
+            Change::Removed { path, .. } => PatchOp::Remove { path },
+            Change::KindChanged { path, new, .. } | Change::ValueChanged { path, new, .. } => {
+                PatchOp::Replace { path, value: new }
+            }
+            Change::LinkChanged { path, new, .. } => PatchOp::Replace {
+                path,
+                value: Ipld::Link(new),
+            },
+        }
+    }
+}
+
+enum Task<'a> {
+    Compare(IpldPath, &'a Ipld, &'a Ipld),
+    Emit(Change),
+}
+
+/// Returns the differences between two IPLD values.
+///
+/// The changes are ordered such that converting them into [`PatchOp`]s in order results in a
+/// valid patch, i.e. elements removed from a List are reported from the back to the front.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{diff::{self, Change}, ipld, patch::{self, PatchOp}};
+///
+/// let old = ipld!({"name": "ipld", "tags": ["a", "b"]});
+/// let new = ipld!({"name": "IPLD", "tags": ["a"]});
+///
+/// let changes = diff::diff(&old, &new);
+/// assert!(matches!(&changes[0], Change::ValueChanged { .. }));
+/// assert_eq!(changes[0].path().to_string(), "/name");
+/// assert!(matches!(&changes[1], Change::Removed { .. }));
+/// assert_eq!(changes[1].path().to_string(), "/tags/1");
+///
+/// let ops: Vec<PatchOp> = changes.into_iter().map(PatchOp::from).collect();
+/// let mut patched = old.clone();
+/// patch::apply(&mut patched, &ops).unwrap();
+/// assert_eq!(patched, new);
+/// ```
+pub fn diff(old: &Ipld, new: &Ipld) -> Vec<Change> {
+    let mut changes = Vec::new();
+    // Use an explicit stack instead of recursion, so that deeply nested values cannot overflow
+    // the call stack.
+    let mut stack = vec![Task::Compare(IpldPath::new(), old, new)];
+
+    while let Some(task) = stack.pop() {
+        let (path, old, new) = match task {
+            Task::Compare(path, old, new) => (path, old, new),
+            Task::Emit(change) => {
+                changes.push(change);
+                continue;
+            }
+        };
+        // The tasks for the children, they are pushed in reverse order on the stack, so that the
+        // changes are reported in document order.
+        let mut tasks = Vec::new();
+        match (old, new) {
+            (Ipld::List(old_list), Ipld::List(new_list)) => {
+                for (index, (old_item, new_item)) in old_list.iter().zip(new_list).enumerate() {
+                    tasks.push(Task::Compare(path.join(index), old_item, new_item));
+                }
+                for (index, item) in new_list.iter().enumerate().skip(old_list.len()) {
+                    tasks.push(Task::Emit(Change::Added {
+                        path: path.join(index),
+                        value: item.clone(),
+                    }));
+                }
+                for (index, item) in old_list.iter().enumerate().skip(new_list.len()).rev() {
+                    tasks.push(Task::Emit(Change::Removed {
+                        path: path.join(index),
+                        value: item.clone(),
+                    }));
+                }
+            }
+            (Ipld::Map(old_map), Ipld::Map(new_map)) => {
+                let mut old_iter = old_map.iter().peekable();
+                let mut new_iter = new_map.iter().peekable();
+                // Both maps are sorted by key, hence a merge gives all keys in order.
+                loop {
+                    let task = match (old_iter.peek(), new_iter.peek()) {
+                        (Some((old_key, _)), Some((new_key, _))) if old_key == new_key => {
+                            let (key, old_value) = old_iter.next().unwrap();
+                            let (_, new_value) = new_iter.next().unwrap();
+                            Task::Compare(path.join(key.as_str()), old_value, new_value)
+                        }
+                        (Some((old_key, _)), Some((new_key, _))) if old_key > new_key => {
+                            let (key, value) = new_iter.next().unwrap();
+                            Task::Emit(Change::Added {
+                                path: path.join(key.as_str()),
+                                value: value.clone(),
+                            })
+                        }
+                        (Some(_), _) => {
+                            let (key, value) = old_iter.next().unwrap();
+                            Task::Emit(Change::Removed {
+                                path: path.join(key.as_str()),
+                                value: value.clone(),
+                            })
+                        }
+                        (None, Some(_)) => {
+                            let (key, value) = new_iter.next().unwrap();
+                            Task::Emit(Change::Added {
+                                path: path.join(key.as_str()),
+                                value: value.clone(),
+                            })
+                        }
+                        (None, None) => break,
+                    };
+                    tasks.push(task);
+                }
+            }
+            (Ipld::Link(old_cid), Ipld::Link(new_cid)) => {
+                if old_cid != new_cid {
+                    changes.push(Change::LinkChanged {
+                        path,
+                        old: *old_cid,
+                        new: *new_cid,
+                    });
+                }
+            }
+            _ if mem::discriminant(old) != mem::discriminant(new) => {
+                changes.push(Change::KindChanged {
+                    path,
+                    old: old.clone(),
+                    new: new.clone(),
+                })
+            }
+            _ => {
+                if old != new {
+                    changes.push(Change::ValueChanged {
+                        path,
+                        old: old.clone(),
+                        new: new.clone(),
+                    });
+                }
+            }
+        }
+        stack.extend(tasks.into_iter().rev());
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{ipld, patch};
+
+    fn path(path: &str) -> IpldPath {
+        path.parse().unwrap()
+    }
+
+    fn assert_patch_roundtrip(old: &Ipld, new: &Ipld) {
+        let ops: Vec<PatchOp> = diff(old, new).into_iter().map(PatchOp::from).collect();
+        let mut patched = old.clone();
+        patch::apply(&mut patched, &ops).unwrap();
+        assert_eq!(&patched, new);
+    }
+
+    #[test]
+    fn test_equal() {
+        let ipld = ipld!({"a": [1, 2.5, "three"], "b": null});
+        assert!(diff(&ipld, &ipld).is_empty());
+        assert!(diff(&Ipld::Float(f64::NAN), &Ipld::Float(f64::NAN)).is_empty());
+    }
+
+    #[test]
+    fn test_changes() {
+        let old_cid =
+            Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+        let new_cid =
+            Cid::try_from("bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily").unwrap();
+        let old = ipld!({"a": 1, "b": "text", "c": old_cid, "d": [1, 2, 3]});
+        let new = ipld!({"a": "1", "b": "other", "c": new_cid, "d": [1], "e": true});
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Change::KindChanged {
+                    path: path("/a"),
+                    old: ipld!(1),
+                    new: ipld!("1"),
+                },
+                Change::ValueChanged {
+                    path: path("/b"),
+                    old: ipld!("text"),
+                    new: ipld!("other"),
+                },
+                Change::LinkChanged {
+                    path: path("/c"),
+                    old: old_cid,
+                    new: new_cid,
+                },
+                Change::Removed {
+                    path: path("/d/2"),
+                    value: ipld!(3),
+                },
+                Change::Removed {
+                    path: path("/d/1"),
+                    value: ipld!(2),
+                },
+                Change::Added {
+                    path: path("/e"),
+                    value: ipld!(true),
+                },
+            ]
+        );
+        assert_patch_roundtrip(&old, &new);
+    }
+
+    #[test]
+    fn test_nested() {
+        let old = ipld!({"list": [{"a": 1}, {"b": 2}], "gone": {"x": 1}});
+        let new = ipld!({"list": [{"a": 2}, {"b": 2}, {"c": 3}, 4], "new": {"y": 2}});
+        let changes = diff(&old, &new);
+        let paths: Vec<_> = changes.iter().map(|change| change.path().clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                path("/gone"),
+                path("/list/0/a"),
+                path("/list/2"),
+                path("/list/3"),
+                path("/new")
+            ]
+        );
+        assert_patch_roundtrip(&old, &new);
+        assert_patch_roundtrip(&new, &old);
+        assert_patch_roundtrip(&old, &ipld!([1]));
+    }
+}
@@ -0,0 +1,231 @@
+//! Computing and verifying CIDs of encoded data.
+//!
+//! The supported hash functions are SHA2-256, the identity hash and, with the `blake3` feature
+//! enabled, BLAKE3.
+use core::fmt;
+
+use cid::{multihash::Multihash, Cid};
+use sha2::Digest;
+
+#[cfg(all(feature = "std", feature = "codec"))]
+use crate::codec::Codec;
+
+/// The maximum size of a digest, it's the size the [`Cid`] type supports.
+const MAX_DIGEST_SIZE: usize = 64;
+
+/// Error when computing or verifying a hash.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum HashError {
+    /// Error when the multihash code isn't supported.
+    UnsupportedCode(u64),
+    /// Error when the data is too large to be used with the identity hash.
+    IdentityTooLarge(usize),
+    /// Error when the hash of the data doesn't match the CID.
+    Mismatch(Cid),
+}
+
+impl fmt::Display for HashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedCode(code) => write!(f, "unsupported multihash code: {:#x}", code),
+            Self::IdentityTooLarge(size) => write!(
+                f,
+                "data of {} bytes is too large for the identity hash, the maximum is {}",
+                size, MAX_DIGEST_SIZE
+            ),
+            Self::Mismatch(cid) => write!(f, "data doesn't match the hash of CID {}", cid),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HashError {}
+
+/// A supported hash function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Code {
+    /// The identity hash, the digest is the data itself.
+    Identity,
+    /// SHA2-256.
+    Sha2_256,
+    /// BLAKE3 with a 256-bit digest.
+    #[cfg(feature = "blake3")]
+    Blake3_256,
+}
+
+impl Code {
+    /// Returns the multihash code of the hash function.
+    pub const fn code(self) -> u64 {
+        match self {
+            Self::Identity => crate::IDENTITY_HASH_CODE,
+            Self::Sha2_256 => 0x12,
+            #[cfg(feature = "blake3")]
+            Self::Blake3_256 => 0x1e,
+        }
+    }
+
+    /// Returns the multihash of the data.
+    pub fn digest(self, data: &[u8]) -> Result<Multihash<MAX_DIGEST_SIZE>, HashError> {
+        let multihash = match self {
+            Self::Identity => Multihash::wrap(self.code(), data)
+                .map_err(|_| HashError::IdentityTooLarge(data.len()))?,
+            Self::Sha2_256 => wrap(self.code(), &sha2::Sha256::digest(data)),
+            #[cfg(feature = "blake3")]
+            Self::Blake3_256 => wrap(self.code(), blake3::hash(data).as_bytes()),
+        };
+        Ok(multihash)
+    }
+}
+
+impl TryFrom<u64> for Code {
+    type Error = HashError;
+
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        match code {
+            crate::IDENTITY_HASH_CODE => Ok(Self::Identity),
+            0x12 => Ok(Self::Sha2_256),
+            #[cfg(feature = "blake3")]
+            0x1e => Ok(Self::Blake3_256),
+            _ => Err(HashError::UnsupportedCode(code)),
+        }
+    }
+}
+
+/// Wraps a digest of a fixed size hash function.
+fn wrap(code: u64, digest: &[u8]) -> Multihash<MAX_DIGEST_SIZE> {
+    Multihash::wrap(code, digest).expect("digest is smaller than the maximum digest size")
+}
+
+/// A hash function that can be selected on the type level.
+pub trait HashFunction {
+    /// The hash function.
+    const CODE: Code;
+}
+
+/// The identity hash function, see [`Code::Identity`].
+#[derive(Clone, Copy, Debug)]
+pub struct Identity;
+
+impl HashFunction for Identity {
+    const CODE: Code = Code::Identity;
+}
+
+/// The SHA2-256 hash function, see [`Code::Sha2_256`].
+#[derive(Clone, Copy, Debug)]
+pub struct Sha2_256;
+
+impl HashFunction for Sha2_256 {
+    const CODE: Code = Code::Sha2_256;
+}
+
+/// The BLAKE3 hash function, see [`Code::Blake3_256`].
+#[cfg(feature = "blake3")]
+#[derive(Clone, Copy, Debug)]
+pub struct Blake3_256;
+
+#[cfg(feature = "blake3")]
+impl HashFunction for Blake3_256 {
+    const CODE: Code = Code::Blake3_256;
+}
+
+/// Returns the CIDv1 of data encoded with the codec of the given multicodec code.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::hash::{self, Code};
+///
+/// // The DAG-CBOR encoded empty map.
+/// let cid = hash::cid_v1(0x71, Code::Sha2_256, &[0xa0]).unwrap();
+/// assert_eq!(
+///     cid.to_string(),
+///     "bafyreigbtj4x7ip5legnfznufuopl4sg4knzc2cof6duas4b3q2fy6swua"
+/// );
+/// ```
+pub fn cid_v1(codec: u64, code: Code, data: &[u8]) -> Result<Cid, HashError> {
+    Ok(Cid::new_v1(codec, code.digest(data)?))
+}
+
+/// Returns the CIDv1 of data encoded with the codec `C`.
+///
+/// The type `T` is the type the data was encoded from, it's needed as codecs may be implemented
+/// for several types.
+#[cfg(all(feature = "std", feature = "codec"))]
+pub fn cid_v1_for_codec<C, T>(code: Code, data: &[u8]) -> Result<Cid, HashError>
+where
+    C: Codec<T>,
+{
+    cid_v1(C::CODE, code, data)
+}
+
+/// Verifies that the data matches the hash of the CID.
+pub fn verify(cid: &Cid, data: &[u8]) -> Result<(), HashError> {
+    let code = Code::try_from(cid.hash().code())?;
+    // Identity hashes with a different length cannot match, don't fail with a misleading error.
+    if code == Code::Identity && data.len() > MAX_DIGEST_SIZE {
+        return Err(HashError::Mismatch(*cid));
+    }
+    if code.digest(data)? != *cid.hash() {
+        return Err(HashError::Mismatch(*cid));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cid_v1() {
+        // The DAG-CBOR encoded string "hello".
+        let data = [0x65, 0x68, 0x65, 0x6c, 0x6c, 0x6f];
+        let cid = cid_v1(0x71, Code::Sha2_256, &data).unwrap();
+        assert_eq!(cid.hash().code(), 0x12);
+        assert_eq!(cid.hash().size(), 32);
+        assert!(verify(&cid, &data).is_ok());
+
+        let cid = cid_v1(0x71, Code::Identity, &data).unwrap();
+        assert_eq!(cid.hash().digest(), data);
+        assert!(verify(&cid, &data).is_ok());
+    }
+
+    #[test]
+    fn test_identity_too_large() {
+        assert!(matches!(
+            cid_v1(0x55, Code::Identity, &[0; 65]),
+            Err(HashError::IdentityTooLarge(65))
+        ));
+        let cid = cid_v1(0x55, Code::Identity, &[0; 64]).unwrap();
+        assert!(matches!(
+            verify(&cid, &[0; 65]),
+            Err(HashError::Mismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify() {
+        let cid = cid_v1(0x55, Code::Sha2_256, b"data").unwrap();
+        assert!(matches!(
+            verify(&cid, b"other"),
+            Err(HashError::Mismatch(mismatch)) if mismatch == cid
+        ));
+
+        let unsupported = Cid::new_v1(0x55, Multihash::wrap(0x13, &[0; 64]).unwrap());
+        assert!(matches!(
+            verify(&unsupported, b"data"),
+            Err(HashError::UnsupportedCode(0x13))
+        ));
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3() {
+        let cid = cid_v1(0x55, Code::Blake3_256, b"data").unwrap();
+        assert_eq!(cid.hash().code(), 0x1e);
+        assert_eq!(cid.hash().digest(), blake3::hash(b"data").as_bytes());
+        assert!(verify(&cid, b"data").is_ok());
+        assert_eq!(Code::try_from(0x1e).unwrap(), Code::Blake3_256);
+    }
+}
@@ -0,0 +1,424 @@
+//! A borrowed counterpart of [`Ipld`].
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    string::String,
+    vec,
+    vec::Vec,
+};
+
+use cid::Cid;
+
+use crate::ipld::{IndexError, Ipld, IpldIndex, IpldKind};
+
+/// A view of IPLD data, whose Strings, Bytes and Map keys may be borrowed.
+///
+/// Deserializing into an `IpldRef` borrows from the input where the format allows it, instead of
+/// copying every String and Bytes value. Use [`IpldRef::to_owned`] to get an [`Ipld`].
+///
+/// Cloning, comparing and converting from and into [`Ipld`] don't recurse, so that they work with
+/// arbitrarily deeply nested values. Debug formatting and dropping do, unlike for [`Ipld`] there
+/// is no iterative alternative, convert untrusted values with [`IpldRef::into_owned`] first.
+///
+/// # Example
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use ipld_core::{codec::Codec, ipld, ipld::IpldRef};
+/// use serde_ipld_dagcbor::codec::DagCborCodec;
+///
+/// let bytes = DagCborCodec::encode_to_vec(&ipld!({"name": "borrowed"})).unwrap();
+/// let ipld: IpldRef = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+/// assert!(matches!(ipld.get("name").unwrap(), Some(IpldRef::String(Cow::Borrowed("borrowed")))));
+/// assert_eq!(ipld.to_owned(), ipld!({"name": "borrowed"}));
+/// ```
+#[derive(Debug)]
+pub enum IpldRef<'a> {
+    /// Represents the absence of a value or the value undefined.
+    Null,
+    /// Represents a boolean value.
+    Bool(bool),
+    /// Represents an integer.
+    Integer(i128),
+    /// Represents a floating point value.
+    Float(f64),
+    /// Represents an UTF-8 string.
+    String(Cow<'a, str>),
+    /// Represents a sequence of bytes.
+    Bytes(Cow<'a, [u8]>),
+    /// Represents a list.
+    List(Vec<IpldRef<'a>>),
+    /// Represents a map of strings.
+    Map(BTreeMap<Cow<'a, str>, IpldRef<'a>>),
+    /// Represents a link to another block.
+    Link(Cid),
+}
+
+impl Clone for IpldRef<'_> {
+    fn clone(&self) -> Self {
+        convert(self, |ipld| match ipld {
+            Self::List(list) => Node::List(list.iter()),
+            Self::Map(map) => Node::Map(map.iter().map(|(key, value)| (key.clone(), value))),
+            Self::Null => Node::Value(Self::Null),
+            Self::Bool(bool) => Node::Value(Self::Bool(*bool)),
+            Self::Integer(integer) => Node::Value(Self::Integer(*integer)),
+            Self::Float(float) => Node::Value(Self::Float(*float)),
+            Self::String(string) => Node::Value(Self::String(string.clone())),
+            Self::Bytes(bytes) => Node::Value(Self::Bytes(bytes.clone())),
+            Self::Link(cid) => Node::Value(Self::Link(*cid)),
+        })
+    }
+}
+
+/// NaN floats compare as equal, the same way as they do for [`Ipld`].
+impl PartialEq for IpldRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        // The pairs of nested values that still need to be compared.
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            let equal = match pair {
+                (Self::Null, Self::Null) => true,
+                (Self::Bool(self_value), Self::Bool(other_value)) => self_value == other_value,
+                (Self::Integer(self_value), Self::Integer(other_value)) => {
+                    self_value == other_value
+                }
+                (Self::Float(self_value), Self::Float(other_value)) => {
+                    // Treat two NaNs as being equal.
+                    self_value == other_value || self_value.is_nan() && other_value.is_nan()
+                }
+                (Self::String(self_value), Self::String(other_value)) => self_value == other_value,
+                (Self::Bytes(self_value), Self::Bytes(other_value)) => self_value == other_value,
+                (Self::List(self_value), Self::List(other_value)) => {
+                    stack.extend(self_value.iter().zip(other_value));
+                    self_value.len() == other_value.len()
+                }
+                (Self::Map(self_value), Self::Map(other_value)) => {
+                    let mut equal = self_value.len() == other_value.len();
+                    for ((self_key, self_child), (other_key, other_child)) in
+                        self_value.iter().zip(other_value)
+                    {
+                        equal &= self_key == other_key;
+                        stack.push((self_child, other_child));
+                    }
+                    equal
+                }
+                (Self::Link(self_value), Self::Link(other_value)) => self_value == other_value,
+                _ => false,
+            };
+            if !equal {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Eq for IpldRef<'_> {}
+
+impl<'a> IpldRef<'a> {
+    /// Returns the kind of the value.
+    pub fn kind(&self) -> IpldKind {
+        match self {
+            Self::Null => IpldKind::Null,
+            Self::Bool(_) => IpldKind::Bool,
+            Self::Integer(_) => IpldKind::Integer,
+            Self::Float(_) => IpldKind::Float,
+            Self::String(_) => IpldKind::String,
+            Self::Bytes(_) => IpldKind::Bytes,
+            Self::List(_) => IpldKind::List,
+            Self::Map(_) => IpldKind::Map,
+            Self::Link(_) => IpldKind::Link,
+        }
+    }
+
+    /// Indexes into an ipld list or map.
+    pub fn get<'b, T: Into<IpldIndex<'b>>>(&self, index: T) -> Result<Option<&Self>, IndexError> {
+        let index = index.into();
+        match self {
+            Self::List(list) => {
+                let parsed_index = usize::try_from(index)?;
+                Ok(list.get(parsed_index))
+            }
+            Self::Map(map) => {
+                let key = String::from(index);
+                Ok(map.get(key.as_str()))
+            }
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Returns an iterator.
+    pub fn iter(&self) -> IpldRefIter<'_, 'a> {
+        IpldRefIter {
+            stack: vec![Box::new(vec![self].into_iter())],
+        }
+    }
+
+    /// Returns the references to other blocks.
+    pub fn references<E: Extend<Cid>>(&self, set: &mut E) {
+        for ipld in self.iter() {
+            if let Self::Link(cid) = ipld {
+                set.extend(core::iter::once(cid.to_owned()));
+            }
+        }
+    }
+
+    /// Copies the borrowed data into an owned [`Ipld`].
+    pub fn to_owned(&self) -> Ipld {
+        convert(self, |ipld| match ipld {
+            Self::Null => Node::Value(Ipld::Null),
+            Self::Bool(bool) => Node::Value(Ipld::Bool(*bool)),
+            Self::Integer(integer) => Node::Value(Ipld::Integer(*integer)),
+            Self::Float(float) => Node::Value(Ipld::Float(*float)),
+            Self::String(string) => Node::Value(Ipld::String(string.as_ref().to_owned())),
+            Self::Bytes(bytes) => Node::Value(Ipld::Bytes(bytes.as_ref().to_owned())),
+            Self::List(list) => Node::List(list.iter()),
+            Self::Map(map) => Node::Map(
+                map.iter()
+                    .map(|(key, value)| (key.as_ref().to_owned(), value)),
+            ),
+            Self::Link(cid) => Node::Value(Ipld::Link(*cid)),
+        })
+    }
+
+    /// Converts into an owned [`Ipld`], data that is already owned isn't copied.
+    pub fn into_owned(self) -> Ipld {
+        convert(self, |ipld| match ipld {
+            Self::Null => Node::Value(Ipld::Null),
+            Self::Bool(bool) => Node::Value(Ipld::Bool(bool)),
+            Self::Integer(integer) => Node::Value(Ipld::Integer(integer)),
+            Self::Float(float) => Node::Value(Ipld::Float(float)),
+            Self::String(string) => Node::Value(Ipld::String(string.into_owned())),
+            Self::Bytes(bytes) => Node::Value(Ipld::Bytes(bytes.into_owned())),
+            Self::List(list) => Node::List(list.into_iter()),
+            Self::Map(map) => Node::Map(
+                map.into_iter()
+                    .map(|(key, value)| (key.into_owned(), value)),
+            ),
+            Self::Link(cid) => Node::Value(Ipld::Link(cid)),
+        })
+    }
+}
+
+impl<'a> From<&'a Ipld> for IpldRef<'a> {
+    fn from(ipld: &'a Ipld) -> Self {
+        convert(ipld, |ipld| match ipld {
+            Ipld::Null => Node::Value(Self::Null),
+            Ipld::Bool(bool) => Node::Value(Self::Bool(*bool)),
+            Ipld::Integer(integer) => Node::Value(Self::Integer(*integer)),
+            Ipld::Float(float) => Node::Value(Self::Float(*float)),
+            Ipld::String(string) => Node::Value(Self::String(Cow::Borrowed(string))),
+            Ipld::Bytes(bytes) => Node::Value(Self::Bytes(Cow::Borrowed(bytes))),
+            Ipld::List(list) => Node::List(list.iter()),
+            Ipld::Map(map) => Node::Map(
+                map.iter()
+                    .map(|(key, value)| (Cow::Borrowed(key.as_str()), value)),
+            ),
+            Ipld::Link(cid) => Node::Value(Self::Link(*cid)),
+        })
+    }
+}
+
+impl From<IpldRef<'_>> for Ipld {
+    fn from(ipld: IpldRef<'_>) -> Self {
+        ipld.into_owned()
+    }
+}
+
+/// A node of the source of [`convert`], either converted directly or the children of a List or
+/// Map that still need to be converted.
+enum Node<T, L, M> {
+    Value(T),
+    List(L),
+    Map(M),
+}
+
+/// Converts into an [`Ipld`] or [`IpldRef`] without recursing.
+///
+/// `node` converts a single node, Lists and Maps are then filled with their converted children.
+fn convert<S, T, K, L, M>(root: S, node: impl Fn(S) -> Node<T, L, M>) -> T
+where
+    T: ListOrMap<K>,
+    K: Ord,
+    L: Iterator<Item = S>,
+    M: Iterator<Item = (K, S)>,
+{
+    /// A List or Map whose children are being converted, together with the key of the child
+    /// that is currently converted.
+    enum Frame<T, K, L, M> {
+        List(L, Vec<T>),
+        Map(M, BTreeMap<K, T>, Option<K>),
+    }
+
+    let mut stack = Vec::new();
+    let mut next = root;
+    loop {
+        let mut value = match node(next) {
+            Node::Value(value) => Some(value),
+            Node::List(iter) => {
+                stack.push(Frame::List(iter, Vec::new()));
+                None
+            }
+            Node::Map(iter) => {
+                stack.push(Frame::Map(iter, BTreeMap::new(), None));
+                None
+            }
+        };
+        // Add the converted value to its parent and find the next value to convert. Parents that
+        // are complete are added to their parent in turn.
+        loop {
+            let child = match stack.last_mut() {
+                None => return value.expect("the root is converted last"),
+                Some(Frame::List(iter, list)) => {
+                    list.extend(value.take());
+                    iter.next()
+                }
+                Some(Frame::Map(iter, map, key)) => {
+                    if let Some(value) = value.take() {
+                        map.insert(key.take().expect("values of maps have a key"), value);
+                    }
+                    iter.next().map(|(child_key, child)| {
+                        *key = Some(child_key);
+                        child
+                    })
+                }
+            };
+            match child {
+                Some(child) => {
+                    next = child;
+                    break;
+                }
+                None => {
+                    value = match stack.pop() {
+                        Some(Frame::List(_, list)) => Some(T::list(list)),
+                        Some(Frame::Map(_, map, _)) => Some(T::map(map)),
+                        None => unreachable!("the stack isn't empty"),
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// The target of [`convert`].
+trait ListOrMap<K>: Sized {
+    fn list(list: Vec<Self>) -> Self;
+    fn map(map: BTreeMap<K, Self>) -> Self;
+}
+
+impl ListOrMap<String> for Ipld {
+    fn list(list: Vec<Self>) -> Self {
+        Self::List(list)
+    }
+
+    fn map(map: BTreeMap<String, Self>) -> Self {
+        Self::Map(map)
+    }
+}
+
+impl<'a> ListOrMap<Cow<'a, str>> for IpldRef<'a> {
+    fn list(list: Vec<Self>) -> Self {
+        Self::List(list)
+    }
+
+    fn map(map: BTreeMap<Cow<'a, str>, Self>) -> Self {
+        Self::Map(map)
+    }
+}
+
+/// IpldRef iterator.
+pub struct IpldRefIter<'b, 'a> {
+    stack: Vec<Box<dyn Iterator<Item = &'b IpldRef<'a>> + 'b>>,
+}
+
+impl<'b, 'a> Iterator for IpldRefIter<'b, 'a> {
+    type Item = &'b IpldRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(iter) = self.stack.last_mut() {
+                if let Some(ipld) = iter.next() {
+                    match ipld {
+                        IpldRef::List(list) => {
+                            self.stack.push(Box::new(list.iter()));
+                        }
+                        IpldRef::Map(map) => {
+                            self.stack.push(Box::new(map.values()));
+                        }
+                        _ => {}
+                    }
+                    return Some(ipld);
+                } else {
+                    self.stack.pop();
+                }
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn test_borrow_roundtrip() {
+        let cid =
+            Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+        let ipld = crate::ipld!({
+            "list": [1, "two", Ipld::Bytes(vec![3]), cid],
+            "map": {"float": 1.5, "null": null, "bool": true},
+        });
+        let borrowed = IpldRef::from(&ipld);
+        assert!(matches!(
+            borrowed.get("list").unwrap().unwrap().get(1),
+            Ok(Some(IpldRef::String(Cow::Borrowed("two"))))
+        ));
+        assert_eq!(borrowed.to_owned(), ipld);
+        assert_eq!(borrowed.clone().into_owned(), ipld);
+        assert_eq!(Ipld::from(borrowed.clone()), ipld);
+
+        assert_eq!(borrowed.iter().count(), ipld.iter().count());
+        let mut references = BTreeSet::new();
+        borrowed.references(&mut references);
+        assert_eq!(references, BTreeSet::from([cid]));
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        let nested = (0..100_000).fold(Ipld::Null, |ipld, _| Ipld::List(vec![ipld]));
+        let borrowed = IpldRef::from(&nested);
+        let clone = borrowed.clone();
+        assert_eq!(borrowed, clone);
+        let owned = borrowed.to_owned();
+        assert_eq!(owned, nested);
+        // Owned values are dropped iteratively.
+        owned.drop_iterative();
+        borrowed.into_owned().drop_iterative();
+        clone.into_owned().drop_iterative();
+        nested.drop_iterative();
+    }
+
+    #[test]
+    fn test_get() {
+        let ipld = crate::ipld!({"list": [1]});
+        let borrowed = IpldRef::from(&ipld);
+        assert_eq!(borrowed.get("missing").unwrap(), None);
+        let list = borrowed.get("list").unwrap().unwrap();
+        assert_eq!(list.get(0).unwrap(), Some(&IpldRef::Integer(1)));
+        assert_eq!(list.get("0").unwrap(), Some(&IpldRef::Integer(1)));
+        assert!(matches!(
+            list.get("a"),
+            Err(IndexError::ParseInteger(key)) if key == "a"
+        ));
+        assert!(matches!(
+            IpldRef::Null.get(0),
+            Err(IndexError::WrongKind(IpldKind::Null))
+        ));
+    }
+}
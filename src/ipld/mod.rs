@@ -0,0 +1,1242 @@
+//! Ipld representation.
+mod borrowed;
+#[cfg(target_has_atomic = "ptr")]
+mod shared;
+
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::{btree_map, BTreeMap},
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+};
+
+use cid::Cid;
+
+use crate::path::parse_list_index;
+
+pub use borrowed::{IpldRef, IpldRefIter};
+#[cfg(target_has_atomic = "ptr")]
+pub use shared::SharedIpld;
+
+/// Error when accessing IPLD List or Map elements.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum IndexError {
+    /// Error when key cannot be parsed into an integer.
+    ParseInteger(String),
+    /// Error when the input wasn't an IPLD List or Map.
+    WrongKind(IpldKind),
+    /// Error when an index is out of bounds of an IPLD List.
+    OutOfBounds(usize),
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParseInteger(key) => write!(f, "cannot parse key into integer: {}", key),
+            Self::WrongKind(kind) => write!(f, "expected IPLD List or Map but found: {:?}", kind),
+            Self::OutOfBounds(index) => write!(f, "index out of bounds: {}", index),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IndexError {}
+
+/// Ipld
+///
+/// Cloning, comparing, hashing and debug formatting don't recurse, so that they work with
+/// arbitrarily deeply nested values. Dropping does recurse, use [`Ipld::drop_iterative`] for
+/// deeply nested values. Deserializing with Serde bounds the nesting depth by default, so that
+/// deserialized values can be dropped normally. Values that this crate discards itself, e.g.
+/// partially deserialized values or the rest of a value in [`Ipld::take_path`], are dropped
+/// iteratively, so is usually the value of the last [`SharedIpld`].
+///
+/// Values are ordered by their [`IpldKind`] first, values of the same kind by their value. Lists
+/// and Maps are compared element by element, Map entries by key, then by value. Floats are
+/// ordered numerically, NaN is equal to NaN and greater than all other floats.
+pub enum Ipld {
+    /// Represents the absence of a value or the value undefined.
+    Null,
+    /// Represents a boolean value.
+    Bool(bool),
+    /// Represents an integer.
+    Integer(i128),
+    /// Represents a floating point value.
+    Float(f64),
+    /// Represents an UTF-8 string.
+    String(String),
+    /// Represents a sequence of bytes.
+    Bytes(Vec<u8>),
+    /// Represents a list.
+    List(Vec<Ipld>),
+    /// Represents a map of strings.
+    Map(BTreeMap<String, Ipld>),
+    /// Represents a map of integers.
+    Link(Cid),
+}
+
+/// A List or Map whose elements are being visited.
+enum Frame<'a, L, M> {
+    List(core::slice::Iter<'a, Ipld>, L),
+    Map(btree_map::Iter<'a, String, Ipld>, M),
+}
+
+impl Clone for Ipld {
+    fn clone(&self) -> Self {
+        // The cloned List or Map of each frame is filled while its elements are visited, `key` is
+        // the key the value that is currently cloned will be inserted with.
+        let mut stack: Vec<Frame<Vec<Ipld>, BTreeMap<String, Ipld>>> = Vec::new();
+        let mut key = Vec::new();
+        let mut next = self;
+        loop {
+            let mut value = match next {
+                Self::List(list) => {
+                    stack.push(Frame::List(list.iter(), Vec::with_capacity(list.len())));
+                    None
+                }
+                Self::Map(map) => {
+                    stack.push(Frame::Map(map.iter(), BTreeMap::new()));
+                    None
+                }
+                Self::Null => Some(Self::Null),
+                Self::Bool(bool) => Some(Self::Bool(*bool)),
+                Self::Integer(integer) => Some(Self::Integer(*integer)),
+                Self::Float(float) => Some(Self::Float(*float)),
+                Self::String(string) => Some(Self::String(string.clone())),
+                Self::Bytes(bytes) => Some(Self::Bytes(bytes.clone())),
+                Self::Link(cid) => Some(Self::Link(*cid)),
+            };
+            // Add the cloned value to its parent and find the next value to clone. Parents that
+            // are complete are added to their parent in turn.
+            loop {
+                let child = match stack.last_mut() {
+                    None => return value.expect("the root is cloned last"),
+                    Some(Frame::List(iter, list)) => {
+                        list.extend(value.take());
+                        iter.next()
+                    }
+                    Some(Frame::Map(iter, map)) => {
+                        if let Some(value) = value.take() {
+                            map.insert(key.pop().expect("values of maps have a key"), value);
+                        }
+                        iter.next().map(|(child_key, child)| {
+                            key.push(child_key.clone());
+                            child
+                        })
+                    }
+                };
+                match child {
+                    Some(child) => {
+                        next = child;
+                        break;
+                    }
+                    None => {
+                        value = match stack.pop() {
+                            Some(Frame::List(_, list)) => Some(Self::List(list)),
+                            Some(Frame::Map(_, map)) => Some(Self::Map(map)),
+                            None => unreachable!("the stack isn't empty"),
+                        };
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Ipld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The output is the same as when formatting Lists and Maps with the `Debug`
+        // implementations of `Vec` and `BTreeMap`. The flag of each frame is whether no element
+        // was written yet.
+        const INDENT: &str = "    ";
+        let alternate = f.alternate();
+        let mut stack: Vec<Frame<bool, bool>> = Vec::new();
+        let mut next = Some(self);
+        loop {
+            if let Some(ipld) = next.take() {
+                match ipld {
+                    Self::List(list) => {
+                        f.write_str(if alternate { "List([" } else { "[" })?;
+                        stack.push(Frame::List(list.iter(), true));
+                    }
+                    Self::Map(map) => {
+                        f.write_str(if alternate { "Map({" } else { "{" })?;
+                        stack.push(Frame::Map(map.iter(), true));
+                    }
+                    scalar if alternate => match scalar {
+                        Self::Null => write!(f, "Null")?,
+                        Self::Bool(b) => write!(f, "Bool({:?})", b)?,
+                        Self::Integer(i) => write!(f, "Integer({:?})", i)?,
+                        Self::Float(i) => write!(f, "Float({:?})", i)?,
+                        Self::String(s) => write!(f, "String({:?})", s)?,
+                        Self::Bytes(b) => write!(f, "Bytes({:?})", b)?,
+                        Self::Link(cid) => write!(f, "Link({})", cid)?,
+                        Self::List(_) | Self::Map(_) => unreachable!(),
+                    },
+                    scalar => match scalar {
+                        Self::Null => write!(f, "null")?,
+                        Self::Bool(b) => write!(f, "{:?}", b)?,
+                        Self::Integer(i) => write!(f, "{:?}", i)?,
+                        Self::Float(i) => write!(f, "{:?}", i)?,
+                        Self::String(s) => write!(f, "{:?}", s)?,
+                        Self::Bytes(b) => write!(f, "{:?}", b)?,
+                        Self::Link(cid) => write!(f, "{}", cid)?,
+                        Self::List(_) | Self::Map(_) => unreachable!(),
+                    },
+                }
+            }
+
+            let depth = stack.len();
+            let (child, key, first) = match stack.last_mut() {
+                None => return Ok(()),
+                Some(Frame::List(iter, first)) => (iter.next(), None, first),
+                Some(Frame::Map(iter, first)) => match iter.next() {
+                    Some((key, child)) => (Some(child), Some(key), first),
+                    None => (None, None, first),
+                },
+            };
+            // Separate the element from the previous one, or close the List or Map.
+            if alternate {
+                if *first {
+                    if child.is_some() {
+                        f.write_str("\n")?;
+                    }
+                } else {
+                    f.write_str(",\n")?;
+                }
+            } else if !*first && child.is_some() {
+                f.write_str(", ")?;
+            }
+            match child {
+                Some(child) => {
+                    *first = false;
+                    if alternate {
+                        for _ in 0..depth {
+                            f.write_str(INDENT)?;
+                        }
+                    }
+                    if let Some(key) = key {
+                        write!(f, "{:?}: ", key)?;
+                    }
+                    next = Some(child);
+                }
+                None => {
+                    if alternate && !*first {
+                        for _ in 1..depth {
+                            f.write_str(INDENT)?;
+                        }
+                    }
+                    let close = match stack.pop() {
+                        Some(Frame::List(..)) if alternate => "])",
+                        Some(Frame::List(..)) => "]",
+                        Some(Frame::Map(..)) if alternate => "})",
+                        _ => "}",
+                    };
+                    f.write_str(close)?;
+                }
+            }
+        }
+    }
+}
+
+/// NaN floats are forbidden in the IPLD Data Model, but we do not enforce it. So in case such a
+/// value is introduced accidentally, make sure that it still compares as equal. This allows us
+/// to implement `Eq` for `Ipld`.
+impl PartialEq for Ipld {
+    fn eq(&self, other: &Self) -> bool {
+        // The pairs of nested values that still need to be compared.
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            let equal = match pair {
+                (Self::Null, Self::Null) => true,
+                (Self::Bool(self_value), Self::Bool(other_value)) => self_value == other_value,
+                (Self::Integer(self_value), Self::Integer(other_value)) => {
+                    self_value == other_value
+                }
+                (Self::Float(self_value), Self::Float(other_value)) => {
+                    // Treat two NaNs as being equal.
+                    self_value == other_value || self_value.is_nan() && other_value.is_nan()
+                }
+                (Self::String(self_value), Self::String(other_value)) => self_value == other_value,
+                (Self::Bytes(self_value), Self::Bytes(other_value)) => self_value == other_value,
+                (Self::List(self_value), Self::List(other_value)) => {
+                    stack.extend(self_value.iter().zip(other_value));
+                    self_value.len() == other_value.len()
+                }
+                (Self::Map(self_value), Self::Map(other_value)) => {
+                    let mut equal = self_value.len() == other_value.len();
+                    for ((self_key, self_child), (other_key, other_child)) in
+                        self_value.iter().zip(other_value)
+                    {
+                        equal &= self_key == other_key;
+                        stack.push((self_child, other_child));
+                    }
+                    equal
+                }
+                (Self::Link(self_value), Self::Link(other_value)) => self_value == other_value,
+                _ => false,
+            };
+            if !equal {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Eq for Ipld {}
+
+impl PartialOrd for Ipld {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ipld {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The Lists and Maps whose elements are being compared.
+        enum Frame<'a> {
+            List(core::slice::Iter<'a, Ipld>, core::slice::Iter<'a, Ipld>),
+            Map(
+                btree_map::Iter<'a, String, Ipld>,
+                btree_map::Iter<'a, String, Ipld>,
+            ),
+        }
+
+        let mut stack = Vec::new();
+        let mut next = Some((self, other));
+        loop {
+            if let Some(pair) = next.take() {
+                let ordering = match pair {
+                    (Self::List(self_value), Self::List(other_value)) => {
+                        stack.push(Frame::List(self_value.iter(), other_value.iter()));
+                        Ordering::Equal
+                    }
+                    (Self::Map(self_value), Self::Map(other_value)) => {
+                        stack.push(Frame::Map(self_value.iter(), other_value.iter()));
+                        Ordering::Equal
+                    }
+                    (Self::Bool(self_value), Self::Bool(other_value)) => {
+                        self_value.cmp(other_value)
+                    }
+                    (Self::Integer(self_value), Self::Integer(other_value)) => {
+                        self_value.cmp(other_value)
+                    }
+                    (Self::Float(self_value), Self::Float(other_value)) => {
+                        match (self_value.is_nan(), other_value.is_nan()) {
+                            (true, true) => Ordering::Equal,
+                            (true, false) => Ordering::Greater,
+                            (false, true) => Ordering::Less,
+                            (false, false) => self_value
+                                .partial_cmp(other_value)
+                                .expect("floats that aren't NaN are comparable"),
+                        }
+                    }
+                    (Self::String(self_value), Self::String(other_value)) => {
+                        self_value.cmp(other_value)
+                    }
+                    (Self::Bytes(self_value), Self::Bytes(other_value)) => {
+                        self_value.cmp(other_value)
+                    }
+                    (Self::Link(self_value), Self::Link(other_value)) => {
+                        self_value.cmp(other_value)
+                    }
+                    (self_value, other_value) => self_value.kind().cmp(&other_value.kind()),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+
+            let (self_child, other_child) = match stack.last_mut() {
+                None => return Ordering::Equal,
+                Some(Frame::List(self_iter, other_iter)) => (self_iter.next(), other_iter.next()),
+                Some(Frame::Map(self_iter, other_iter)) => {
+                    match (self_iter.next(), other_iter.next()) {
+                        (Some((self_key, self_child)), Some((other_key, other_child))) => {
+                            let ordering = self_key.cmp(other_key);
+                            if ordering != Ordering::Equal {
+                                return ordering;
+                            }
+                            (Some(self_child), Some(other_child))
+                        }
+                        (self_entry, other_entry) => (
+                            self_entry.map(|(_, child)| child),
+                            other_entry.map(|(_, child)| child),
+                        ),
+                    }
+                }
+            };
+            match (self_child, other_child) {
+                (Some(self_child), Some(other_child)) => next = Some((self_child, other_child)),
+                (Some(_), None) => return Ordering::Greater,
+                (None, Some(_)) => return Ordering::Less,
+                (None, None) => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl Hash for Ipld {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Map keys are hashed right before their values.
+        enum Item<'a> {
+            Value(&'a Ipld),
+            Key(&'a String),
+        }
+
+        let mut stack = vec![Item::Value(self)];
+        while let Some(item) = stack.pop() {
+            let ipld = match item {
+                Item::Value(ipld) => ipld,
+                Item::Key(key) => {
+                    key.hash(state);
+                    continue;
+                }
+            };
+            ipld.kind().hash(state);
+            match ipld {
+                Self::Null => {}
+                Self::Bool(bool) => bool.hash(state),
+                Self::Integer(integer) => integer.hash(state),
+                Self::Float(float) => {
+                    // Floats that are equal need to have the same hash, this includes all NaNs
+                    // as well as positive and negative zero.
+                    let float = if float.is_nan() {
+                        f64::NAN
+                    } else if *float == 0.0 {
+                        0.0
+                    } else {
+                        *float
+                    };
+                    float.to_bits().hash(state)
+                }
+                Self::String(string) => string.hash(state),
+                Self::Bytes(bytes) => bytes.hash(state),
+                Self::List(list) => {
+                    list.len().hash(state);
+                    stack.extend(list.iter().rev().map(Item::Value));
+                }
+                Self::Map(map) => {
+                    map.len().hash(state);
+                    for (key, value) in map.iter().rev() {
+                        stack.push(Item::Value(value));
+                        stack.push(Item::Key(key));
+                    }
+                }
+                Self::Link(cid) => Hash::hash(cid, state),
+            }
+        }
+    }
+}
+
+/// IPLD Kind information without the actual value.
+///
+/// Sometimes it's useful to know the kind of an Ipld object without the actual value, e.g. for
+/// error reporting. Those kinds can be a unity-only enum.
+///
+/// The kinds are ordered the way they are declared, it's the order of [`Ipld`] values of different
+/// kinds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IpldKind {
+    /// Null type.
+    Null,
+    /// Boolean type.
+    Bool,
+    /// Integer type.
+    Integer,
+    /// Float type.
+    Float,
+    /// String type.
+    String,
+    /// Bytes type.
+    Bytes,
+    /// List type.
+    List,
+    /// Map type.
+    Map,
+    /// Link type.
+    Link,
+}
+
+impl fmt::Display for IpldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Null => "Null",
+            Self::Bool => "Bool",
+            Self::Integer => "Integer",
+            Self::Float => "Float",
+            Self::String => "String",
+            Self::Bytes => "Bytes",
+            Self::List => "List",
+            Self::Map => "Map",
+            Self::Link => "Link",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// An index into IPLD.
+///
+/// It's used for accessing IPLD List and Map elements. A string used on a List is parsed into an
+/// integer, like the segments of an [`IpldPath`](crate::path::IpldPath) only the canonical decimal
+/// representation is accepted, i.e. no sign and no leading zeros.
+pub enum IpldIndex<'a> {
+    /// An index into an ipld list.
+    List(usize),
+    /// An owned index into an ipld map.
+    Map(String),
+    /// An index into an ipld map.
+    MapRef(&'a str),
+}
+
+impl From<usize> for IpldIndex<'_> {
+    fn from(index: usize) -> Self {
+        Self::List(index)
+    }
+}
+
+impl From<String> for IpldIndex<'_> {
+    fn from(key: String) -> Self {
+        Self::Map(key)
+    }
+}
+
+impl<'a> From<&'a str> for IpldIndex<'a> {
+    fn from(key: &'a str) -> Self {
+        Self::MapRef(key)
+    }
+}
+
+impl<'a> TryFrom<IpldIndex<'a>> for usize {
+    type Error = IndexError;
+
+    fn try_from(index: IpldIndex<'a>) -> Result<Self, Self::Error> {
+        let parsed =
+            match index {
+                IpldIndex::List(i) => i,
+                IpldIndex::Map(ref key) => parse_list_index(key)
+                    .ok_or_else(|| IndexError::ParseInteger(key.to_string()))?,
+                IpldIndex::MapRef(key) => parse_list_index(key)
+                    .ok_or_else(|| IndexError::ParseInteger(key.to_string()))?,
+            };
+        Ok(parsed)
+    }
+}
+
+impl<'a> From<IpldIndex<'a>> for String {
+    fn from(index: IpldIndex<'a>) -> Self {
+        match index {
+            IpldIndex::Map(ref key) => key.to_string(),
+            IpldIndex::MapRef(key) => key.to_string(),
+            IpldIndex::List(i) => i.to_string(),
+        }
+    }
+}
+
+impl Ipld {
+    /// Convert from an [`Ipld`] object into its kind without any associated values.
+    ///
+    /// This is intentionally not implemented via `From<Ipld>` to prevent accidental conversions by
+    /// making it more explicit.
+    pub fn kind(&self) -> IpldKind {
+        match self {
+            Ipld::Null => IpldKind::Null,
+            Ipld::Bool(_) => IpldKind::Bool,
+            Ipld::Integer(_) => IpldKind::Integer,
+            Ipld::Float(_) => IpldKind::Float,
+            Ipld::String(_) => IpldKind::String,
+            Ipld::Bytes(_) => IpldKind::Bytes,
+            Ipld::List(_) => IpldKind::List,
+            Ipld::Map(_) => IpldKind::Map,
+            Ipld::Link(_) => IpldKind::Link,
+        }
+    }
+
+    /// Destructs an ipld list or map
+    ///
+    /// Only the requested element is kept, all other elements are dropped. In order to remove an
+    /// element while keeping the remaining ones in their original order, use [`Ipld::remove`].
+    pub fn take<'a, T: Into<IpldIndex<'a>>>(
+        mut self,
+        index: T,
+    ) -> Result<Option<Self>, IndexError> {
+        let index = index.into();
+        match &mut self {
+            Ipld::List(ref mut list) => {
+                let parsed_index = usize::try_from(index)?;
+                if parsed_index < list.len() {
+                    Ok(Some(list.swap_remove(parsed_index)))
+                } else {
+                    Ok(None)
+                }
+            }
+            Ipld::Map(ref mut map) => {
+                let key = String::from(index);
+                Ok(map.remove(&key))
+            }
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Indexes into an ipld list or map.
+    pub fn get<'a, T: Into<IpldIndex<'a>>>(&self, index: T) -> Result<Option<&Self>, IndexError> {
+        let index = index.into();
+        match self {
+            Ipld::List(list) => {
+                let parsed_index = usize::try_from(index)?;
+                Ok(list.get(parsed_index))
+            }
+            Ipld::Map(map) => {
+                let key = String::from(index);
+                Ok(map.get(&key))
+            }
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Mutably indexes into an ipld list or map.
+    pub fn get_mut<'a, T: Into<IpldIndex<'a>>>(
+        &mut self,
+        index: T,
+    ) -> Result<Option<&mut Self>, IndexError> {
+        let index = index.into();
+        match self {
+            Ipld::List(list) => {
+                let parsed_index = usize::try_from(index)?;
+                Ok(list.get_mut(parsed_index))
+            }
+            Ipld::Map(map) => {
+                let key = String::from(index);
+                Ok(map.get_mut(&key))
+            }
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Inserts a value into an ipld list or map.
+    ///
+    /// For maps, the value is stored under the given key and the previous value is returned. For
+    /// lists, the value is inserted at the given position and all elements after it are shifted
+    /// to the right. Inserting into a list never returns a previous value.
+    pub fn insert<'a, T: Into<IpldIndex<'a>>>(
+        &mut self,
+        index: T,
+        value: Self,
+    ) -> Result<Option<Self>, IndexError> {
+        let index = index.into();
+        match self {
+            Ipld::List(list) => {
+                let parsed_index = usize::try_from(index)?;
+                if parsed_index <= list.len() {
+                    list.insert(parsed_index, value);
+                    Ok(None)
+                } else {
+                    Err(IndexError::OutOfBounds(parsed_index))
+                }
+            }
+            Ipld::Map(map) => {
+                let key = String::from(index);
+                Ok(map.insert(key, value))
+            }
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Removes a value from an ipld list or map.
+    ///
+    /// Removing from a list preserves the order of the remaining elements.
+    pub fn remove<'a, T: Into<IpldIndex<'a>>>(
+        &mut self,
+        index: T,
+    ) -> Result<Option<Self>, IndexError> {
+        let index = index.into();
+        match self {
+            Ipld::List(list) => {
+                let parsed_index = usize::try_from(index)?;
+                if parsed_index < list.len() {
+                    Ok(Some(list.remove(parsed_index)))
+                } else {
+                    Ok(None)
+                }
+            }
+            Ipld::Map(map) => {
+                let key = String::from(index);
+                Ok(map.remove(&key))
+            }
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Appends a value to the end of an ipld list.
+    pub fn push(&mut self, value: Self) -> Result<(), IndexError> {
+        match self {
+            Ipld::List(list) => {
+                list.push(value);
+                Ok(())
+            }
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Returns the entry of an ipld map for in-place manipulation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ipld_core::{ipld, ipld::Ipld};
+    ///
+    /// let mut counts = ipld!({"a": 1});
+    /// for key in ["a", "b"] {
+    ///     let count = counts.entry(key).unwrap().or_insert(Ipld::Integer(0));
+    ///     if let Ipld::Integer(count) = count {
+    ///         *count += 1;
+    ///     }
+    /// }
+    /// assert_eq!(counts, ipld!({"a": 2, "b": 1}));
+    /// ```
+    pub fn entry<K: Into<String>>(
+        &mut self,
+        key: K,
+    ) -> Result<btree_map::Entry<'_, String, Self>, IndexError> {
+        match self {
+            Ipld::Map(map) => Ok(map.entry(key.into())),
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Returns an owning iterator over the elements of an ipld list.
+    pub fn into_list_iter(self) -> Result<vec::IntoIter<Self>, IndexError> {
+        match self {
+            Ipld::List(list) => Ok(list.into_iter()),
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Returns an owning iterator over the entries of an ipld map.
+    pub fn into_map_iter(self) -> Result<btree_map::IntoIter<String, Self>, IndexError> {
+        match self {
+            Ipld::Map(map) => Ok(map.into_iter()),
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Removes all elements from an ipld list and returns them in order as an iterator.
+    ///
+    /// The list is left empty, even if the iterator isn't fully consumed.
+    pub fn drain_list(&mut self) -> Result<vec::Drain<'_, Self>, IndexError> {
+        match self {
+            Ipld::List(list) => Ok(list.drain(..)),
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Removes all entries from an ipld map and returns them as an iterator.
+    ///
+    /// The map is left empty.
+    pub fn drain_map(&mut self) -> Result<btree_map::IntoIter<String, Self>, IndexError> {
+        match self {
+            Ipld::Map(map) => Ok(mem::take(map).into_iter()),
+            other => Err(IndexError::WrongKind(other.kind())),
+        }
+    }
+
+    /// Drops the value without recursing into nested Lists and Maps.
+    ///
+    /// The default drop recurses once per level of nesting, which can overflow the stack for
+    /// untrusted, deeply nested values. `Ipld` doesn't implement [`Drop`] itself, as it would
+    /// prevent moving values out of it, hence values that are returned to the caller still need to
+    /// be dropped with this method.
+    pub fn drop_iterative(self) {
+        let mut stack = vec![self];
+        while let Some(ipld) = stack.pop() {
+            match ipld {
+                Self::List(list) => stack.extend(list),
+                Self::Map(map) => stack.extend(map.into_values()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns an iterator.
+    pub fn iter(&self) -> IpldIter<'_> {
+        IpldIter {
+            stack: vec![Box::new(vec![self].into_iter())],
+        }
+    }
+
+    /// Returns the references to other blocks.
+    pub fn references<E: Extend<Cid>>(&self, set: &mut E) {
+        for ipld in self.iter() {
+            if let Ipld::Link(cid) = ipld {
+                set.extend(core::iter::once(cid.to_owned()));
+            }
+        }
+    }
+}
+
+/// Ipld iterator.
+pub struct IpldIter<'a> {
+    stack: Vec<Box<dyn Iterator<Item = &'a Ipld> + 'a>>,
+}
+
+impl<'a> Iterator for IpldIter<'a> {
+    type Item = &'a Ipld;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(iter) = self.stack.last_mut() {
+                if let Some(ipld) = iter.next() {
+                    match ipld {
+                        Ipld::List(list) => {
+                            self.stack.push(Box::new(list.iter()));
+                        }
+                        Ipld::Map(map) => {
+                            self.stack.push(Box::new(map.values()));
+                        }
+                        _ => {}
+                    }
+                    return Some(ipld);
+                } else {
+                    self.stack.pop();
+                }
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipld_bool_from() {
+        assert_eq!(Ipld::Bool(true), Ipld::from(true));
+        assert_eq!(Ipld::Bool(false), Ipld::from(false));
+    }
+
+    #[test]
+    fn test_ipld_integer_from() {
+        assert_eq!(Ipld::Integer(1), Ipld::from(1i8));
+        assert_eq!(Ipld::Integer(1), Ipld::from(1i16));
+        assert_eq!(Ipld::Integer(1), Ipld::from(1i32));
+        assert_eq!(Ipld::Integer(1), Ipld::from(1i64));
+        assert_eq!(Ipld::Integer(1), Ipld::from(1i128));
+
+        //assert_eq!(Ipld::Integer(1), 1u8.to_ipld().to_owned());
+        assert_eq!(Ipld::Integer(1), Ipld::from(1u16));
+        assert_eq!(Ipld::Integer(1), Ipld::from(1u32));
+        assert_eq!(Ipld::Integer(1), Ipld::from(1u64));
+    }
+
+    #[test]
+    fn test_ipld_float_from() {
+        assert_eq!(Ipld::Float(1.0), Ipld::from(1.0f32));
+        assert_eq!(Ipld::Float(1.0), Ipld::from(1.0f64));
+    }
+
+    #[test]
+    fn test_ipld_string_from() {
+        assert_eq!(Ipld::String("a string".into()), Ipld::from("a string"));
+        assert_eq!(
+            Ipld::String("a string".into()),
+            Ipld::from("a string".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ipld_bytes_from() {
+        assert_eq!(
+            Ipld::Bytes(vec![0, 1, 2, 3]),
+            Ipld::from(&[0u8, 1u8, 2u8, 3u8][..])
+        );
+        assert_eq!(
+            Ipld::Bytes(vec![0, 1, 2, 3]),
+            Ipld::from(vec![0u8, 1u8, 2u8, 3u8])
+        );
+    }
+
+    #[test]
+    fn test_ipld_link_from() {
+        let cid =
+            Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+        assert_eq!(Ipld::Link(cid), Ipld::from(cid));
+    }
+
+    #[test]
+    fn test_take() {
+        let ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(1), Ipld::Integer(2)]);
+        assert_eq!(ipld.clone().take(0).unwrap(), Some(Ipld::Integer(0)));
+        assert_eq!(ipld.clone().take(1).unwrap(), Some(Ipld::Integer(1)));
+        assert_eq!(ipld.take(2).unwrap(), Some(Ipld::Integer(2)));
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Ipld::Integer(0));
+        map.insert("b".to_string(), Ipld::Integer(1));
+        map.insert("c".to_string(), Ipld::Integer(2));
+        let ipld = Ipld::Map(map);
+        assert_eq!(ipld.take("a").unwrap(), Some(Ipld::Integer(0)));
+    }
+
+    #[test]
+    fn test_get() {
+        let ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(1), Ipld::Integer(2)]);
+        assert_eq!(ipld.get(0).unwrap(), Some(&Ipld::Integer(0)));
+        assert_eq!(ipld.get(1).unwrap(), Some(&Ipld::Integer(1)));
+        assert_eq!(ipld.get(2).unwrap(), Some(&Ipld::Integer(2)));
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Ipld::Integer(0));
+        map.insert("b".to_string(), Ipld::Integer(1));
+        map.insert("c".to_string(), Ipld::Integer(2));
+        let ipld = Ipld::Map(map);
+        assert_eq!(ipld.get("a").unwrap(), Some(&Ipld::Integer(0)));
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(1)]);
+        *ipld.get_mut(1).unwrap().unwrap() = Ipld::Integer(2);
+        assert_eq!(ipld, Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(2)]));
+        assert_eq!(ipld.get_mut(2).unwrap(), None);
+
+        let mut ipld = Ipld::Map(BTreeMap::from([("a".to_string(), Ipld::Integer(0))]));
+        *ipld.get_mut("a").unwrap().unwrap() = Ipld::Null;
+        assert_eq!(ipld.get("a").unwrap(), Some(&Ipld::Null));
+
+        assert!(matches!(
+            Ipld::Null.get_mut(0),
+            Err(IndexError::WrongKind(IpldKind::Null))
+        ));
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(2)]);
+        assert_eq!(ipld.insert(1, Ipld::Integer(1)).unwrap(), None);
+        assert_eq!(ipld.insert(3, Ipld::Integer(3)).unwrap(), None);
+        assert!(matches!(
+            ipld.insert(5, Ipld::Integer(5)),
+            Err(IndexError::OutOfBounds(5))
+        ));
+        assert_eq!(ipld.remove(0).unwrap(), Some(Ipld::Integer(0)));
+        assert_eq!(ipld.remove(3).unwrap(), None);
+        ipld.push(Ipld::Integer(4)).unwrap();
+        // String indices must be canonical, like the segments of a path.
+        for index in ["+1", "01", "-0", ""] {
+            assert!(matches!(
+                ipld.insert(index, Ipld::Null),
+                Err(IndexError::ParseInteger(_))
+            ));
+            assert!(matches!(
+                ipld.remove(index),
+                Err(IndexError::ParseInteger(_))
+            ));
+        }
+        assert_eq!(ipld.get("1").unwrap(), Some(&Ipld::Integer(2)));
+        assert_eq!(
+            ipld,
+            Ipld::List(vec![
+                Ipld::Integer(1),
+                Ipld::Integer(2),
+                Ipld::Integer(3),
+                Ipld::Integer(4)
+            ])
+        );
+
+        let mut ipld = Ipld::Map(BTreeMap::new());
+        assert_eq!(ipld.insert("a", Ipld::Integer(0)).unwrap(), None);
+        assert_eq!(
+            ipld.insert("a", Ipld::Integer(1)).unwrap(),
+            Some(Ipld::Integer(0))
+        );
+        assert!(ipld.push(Ipld::Null).is_err());
+        assert_eq!(ipld.remove("a").unwrap(), Some(Ipld::Integer(1)));
+        assert_eq!(ipld.remove("a").unwrap(), None);
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut ipld = Ipld::Map(BTreeMap::new());
+        ipld.entry("a").unwrap().or_insert(Ipld::Integer(0));
+        ipld.entry("a").unwrap().or_insert(Ipld::Integer(1));
+        assert_eq!(ipld.get("a").unwrap(), Some(&Ipld::Integer(0)));
+        assert!(Ipld::List(vec![]).entry("a").is_err());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(1), Ipld::Integer(2)]);
+        let list: Vec<Ipld> = ipld.into_list_iter().unwrap().collect();
+        assert_eq!(
+            list,
+            vec![Ipld::Integer(0), Ipld::Integer(1), Ipld::Integer(2)]
+        );
+        assert!(Ipld::Null.into_list_iter().is_err());
+
+        let mut map = BTreeMap::new();
+        map.insert("b".to_string(), Ipld::Integer(1));
+        map.insert("a".to_string(), Ipld::Integer(0));
+        let entries: Vec<(String, Ipld)> = Ipld::Map(map).into_map_iter().unwrap().collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("a".to_string(), Ipld::Integer(0)),
+                ("b".to_string(), Ipld::Integer(1))
+            ]
+        );
+        assert!(Ipld::List(vec![]).into_map_iter().is_err());
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut ipld = Ipld::List(vec![Ipld::Integer(0), Ipld::Integer(1)]);
+        let drained: Vec<Ipld> = ipld.drain_list().unwrap().collect();
+        assert_eq!(drained, vec![Ipld::Integer(0), Ipld::Integer(1)]);
+        assert_eq!(ipld, Ipld::List(vec![]));
+
+        let mut map = BTreeMap::new();
+        map.insert("a".to_string(), Ipld::Integer(0));
+        let mut ipld = Ipld::Map(map);
+        let drained: Vec<(String, Ipld)> = ipld.drain_map().unwrap().collect();
+        assert_eq!(drained, vec![("a".to_string(), Ipld::Integer(0))]);
+        assert_eq!(ipld, Ipld::Map(BTreeMap::new()));
+        assert!(ipld.drain_list().is_err());
+    }
+
+    // NaN floats are forbidden in the IPLD Data Model, but still make sure they are treated as
+    // equal in case they accidentally end up there.
+    #[test]
+    fn test_partial_eq_nan() {
+        let invalid_ipld = Ipld::Float(f64::NAN);
+        assert_eq!(invalid_ipld, invalid_ipld);
+    }
+
+    fn sample() -> Ipld {
+        let cid =
+            Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+        crate::ipld!({
+            "list": [1, "two\n", [], [null, true, 1.5]],
+            "map": {},
+            "bytes": Ipld::Bytes(vec![1, 2]),
+            "link": cid,
+            "nested": {"a": {"b": [-1]}},
+        })
+    }
+
+    /// Returns a List that is nested `depth` times, with a Map in the innermost List.
+    fn deeply_nested(depth: usize) -> Ipld {
+        let mut ipld = crate::ipld!({"leaf": [1, 2]});
+        for _ in 0..depth {
+            ipld = Ipld::List(vec![ipld, Ipld::Null]);
+        }
+        ipld
+    }
+
+    #[test]
+    fn test_debug() {
+        let ipld = sample();
+        assert_eq!(
+            format!("{:?}", ipld),
+            r#"{"bytes": [1, 2], "link": bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m, "list": [1, "two\n", [], [null, true, 1.5]], "map": {}, "nested": {"a": {"b": [-1]}}}"#
+        );
+        assert_eq!(
+            format!("{:#?}", ipld),
+            r#"Map({
+    "bytes": Bytes([1, 2]),
+    "link": Link(bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m),
+    "list": List([
+        Integer(1),
+        String("two\n"),
+        List([]),
+        List([
+            Null,
+            Bool(true),
+            Float(1.5),
+        ]),
+    ]),
+    "map": Map({}),
+    "nested": Map({
+        "a": Map({
+            "b": List([
+                Integer(-1),
+            ]),
+        }),
+    }),
+})"#
+        );
+        assert_eq!(format!("{:?}", Ipld::List(vec![])), "[]");
+        assert_eq!(format!("{:#?}", Ipld::Map(BTreeMap::new())), "Map({})");
+        assert_eq!(format!("{:#?}", Ipld::Integer(1)), "Integer(1)");
+    }
+
+    #[test]
+    fn test_clone_eq() {
+        let ipld = sample();
+        assert_eq!(ipld.clone(), ipld);
+        let mut other = ipld.clone();
+        let nested = other.get_mut("nested").unwrap().unwrap();
+        *nested.get_mut("a").unwrap().unwrap() = Ipld::Null;
+        assert_ne!(other, ipld);
+        assert_ne!(Ipld::List(vec![Ipld::Null]), Ipld::List(vec![]));
+        assert_ne!(crate::ipld!({"a": 1}), crate::ipld!({"b": 1}));
+        assert_eq!(Ipld::Float(f64::NAN), Ipld::Float(f64::NAN));
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        let ipld = deeply_nested(100_000);
+        let clone = ipld.clone();
+        assert_eq!(clone, ipld);
+        let shallower = deeply_nested(99_999);
+        assert_ne!(clone, shallower);
+
+        let debug = format!("{:?}", ipld);
+        assert_eq!(
+            debug.len(),
+            100_000 * "[, null]".len() + r#"{"leaf": [1, 2]}"#.len()
+        );
+        assert!(debug.contains(r#"[[{"leaf": [1, 2]}, null], null]"#));
+        // Pretty printing indents each level, so the output grows quadratically.
+        let pretty = format!("{:#?}", deeply_nested(1000));
+        assert_eq!(pretty.lines().count(), 1000 * 3 + 6);
+
+        shallower.drop_iterative();
+        clone.drop_iterative();
+        ipld.drop_iterative();
+    }
+
+    #[test]
+    fn test_ord() {
+        let cid =
+            Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+        // Sorted by kind first.
+        let sorted = vec![
+            Ipld::Null,
+            Ipld::Bool(false),
+            Ipld::Bool(true),
+            Ipld::Integer(-1),
+            Ipld::Integer(2),
+            Ipld::Float(f64::NEG_INFINITY),
+            Ipld::Float(-0.5),
+            Ipld::Float(1.5),
+            Ipld::Float(f64::NAN),
+            Ipld::String("a".into()),
+            Ipld::String("b".into()),
+            Ipld::Bytes(vec![]),
+            Ipld::Bytes(vec![0]),
+            crate::ipld!([]),
+            crate::ipld!([1]),
+            crate::ipld!([1, 2]),
+            crate::ipld!([2]),
+            crate::ipld!({}),
+            crate::ipld!({"a": 2}),
+            crate::ipld!({"a": 2, "b": 1}),
+            crate::ipld!({"a": 3}),
+            crate::ipld!({"b": 1}),
+            Ipld::Link(cid),
+        ];
+        for (index, ipld) in sorted.iter().enumerate() {
+            assert_eq!(ipld.cmp(ipld), Ordering::Equal);
+            for other in &sorted[index + 1..] {
+                assert_eq!(ipld.cmp(other), Ordering::Less, "{:?} < {:?}", ipld, other);
+                assert_eq!(other.cmp(ipld), Ordering::Greater);
+            }
+        }
+        let mut reversed = sorted.clone();
+        reversed.reverse();
+        reversed.sort();
+        assert_eq!(reversed, sorted);
+
+        // The order is consistent with equality.
+        assert_eq!(
+            Ipld::Float(f64::NAN).cmp(&Ipld::Float(-f64::NAN)),
+            Ordering::Equal
+        );
+        assert_eq!(Ipld::Float(0.0).cmp(&Ipld::Float(-0.0)), Ordering::Equal);
+        assert_eq!(Ipld::Float(0.0), Ipld::Float(-0.0));
+    }
+
+    #[test]
+    fn test_set_keys() {
+        let set = alloc::collections::BTreeSet::from([
+            crate::ipld!({"a": [1, 2]}),
+            crate::ipld!({"a": [1, 2]}),
+            Ipld::Float(f64::NAN),
+            Ipld::Float(f64::NAN),
+            Ipld::Null,
+        ]);
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.first(), Some(&Ipld::Null));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hash() {
+        use std::{collections::HashSet, hash::BuildHasher};
+
+        let hasher = std::collections::hash_map::RandomState::new();
+        assert_eq!(
+            hasher.hash_one(Ipld::Float(0.0)),
+            hasher.hash_one(Ipld::Float(-0.0))
+        );
+        assert_eq!(
+            hasher.hash_one(Ipld::Float(f64::NAN)),
+            hasher.hash_one(Ipld::Float(-f64::NAN))
+        );
+        // The structure is part of the hash.
+        assert_ne!(
+            hasher.hash_one(crate::ipld!([[1], 2])),
+            hasher.hash_one(crate::ipld!([[1, 2]]))
+        );
+        assert_ne!(
+            hasher.hash_one(crate::ipld!({"a": "b"})),
+            hasher.hash_one(crate::ipld!({"ab": ""}))
+        );
+
+        let set = HashSet::from([
+            crate::ipld!({"a": [1, 2]}),
+            crate::ipld!({"a": [1, 2]}),
+            crate::ipld!({"a": [1, 3]}),
+            Ipld::Float(0.0),
+            Ipld::Float(-0.0),
+        ]);
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn test_deeply_nested_ord() {
+        let ipld = deeply_nested(100_000);
+        let shallower = deeply_nested(99_999);
+        assert_eq!(ipld.cmp(&ipld), Ordering::Equal);
+        // The innermost Map of the shallower one is compared with a List, which is less.
+        assert_eq!(ipld.cmp(&shallower), Ordering::Less);
+        #[cfg(feature = "std")]
+        {
+            use std::hash::BuildHasher;
+            let hasher = std::collections::hash_map::RandomState::new();
+            assert_ne!(hasher.hash_one(&ipld), hasher.hash_one(&shallower));
+        }
+        shallower.drop_iterative();
+        ipld.drop_iterative();
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(Ipld::Bytes(vec![]).kind().to_string(), "Bytes");
+        assert!(IpldKind::Null < IpldKind::Link);
+    }
+}
@@ -0,0 +1,162 @@
+//! A reference counted [`Ipld`], which is cheap to clone.
+use alloc::sync::Arc;
+use core::{fmt, mem, ops::Deref};
+
+use crate::ipld::Ipld;
+
+/// An [`Ipld`] value that is shared between its clones.
+///
+/// Cloning only increments a reference count, the value is copied when it's mutated through
+/// [`SharedIpld::make_mut`] while it's shared. It dereferences to [`Ipld`] for read access.
+///
+/// When the last clone is dropped, the value is usually dropped with [`Ipld::drop_iterative`].
+/// It's dropped recursively if the last clones are dropped concurrently on different threads, or
+/// if a [`Weak`](alloc::sync::Weak) of the `Arc` it was created from still exists.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{ipld, ipld::SharedIpld};
+///
+/// let shared = SharedIpld::from(ipld!({"name": "cached"}));
+/// let mut clone = shared.clone();
+/// assert!(SharedIpld::ptr_eq(&shared, &clone));
+/// assert_eq!(clone.get("name").unwrap(), Some(&ipld!("cached")));
+///
+/// // Mutating a shared value copies it first.
+/// clone.make_mut().insert("name", ipld!("changed")).unwrap();
+/// assert!(!SharedIpld::ptr_eq(&shared, &clone));
+/// assert_eq!(shared.into_ipld(), ipld!({"name": "cached"}));
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedIpld(Arc<Ipld>);
+
+impl SharedIpld {
+    /// Creates a shared value.
+    pub fn new(ipld: Ipld) -> Self {
+        Self(Arc::new(ipld))
+    }
+
+    /// Returns a mutable reference to the value, it's copied first if it's shared.
+    pub fn make_mut(&mut self) -> &mut Ipld {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Returns the value, it's copied if it's shared.
+    pub fn into_ipld(mut self) -> Ipld {
+        match Arc::get_mut(&mut self.0) {
+            Some(ipld) => mem::replace(ipld, Ipld::Null),
+            None => (*self.0).clone(),
+        }
+    }
+
+    /// Returns whether both point to the same value.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl Drop for SharedIpld {
+    fn drop(&mut self) {
+        // This is checked before the reference count is decremented, hence clones that are dropped
+        // concurrently may both see the other one and leave the value to the recursive drop.
+        if let Some(ipld) = Arc::get_mut(&mut self.0) {
+            mem::replace(ipld, Ipld::Null).drop_iterative();
+        }
+    }
+}
+
+impl Deref for SharedIpld {
+    type Target = Ipld;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Ipld> for SharedIpld {
+    fn as_ref(&self) -> &Ipld {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SharedIpld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl From<Ipld> for SharedIpld {
+    fn from(ipld: Ipld) -> Self {
+        Self::new(ipld)
+    }
+}
+
+impl From<Arc<Ipld>> for SharedIpld {
+    fn from(ipld: Arc<Ipld>) -> Self {
+        Self(ipld)
+    }
+}
+
+impl From<SharedIpld> for Ipld {
+    fn from(shared: SharedIpld) -> Self {
+        shared.into_ipld()
+    }
+}
+
+/// The returned `Arc` drops the value recursively, like a plain [`Ipld`].
+impl From<SharedIpld> for Arc<Ipld> {
+    fn from(shared: SharedIpld) -> Self {
+        Arc::clone(&shared.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared() {
+        let ipld = crate::ipld!({"list": [1, 2]});
+        let shared = SharedIpld::from(ipld.clone());
+        let clone = shared.clone();
+        assert!(SharedIpld::ptr_eq(&shared, &clone));
+        assert_eq!(*clone, ipld);
+        assert_eq!(format!("{:?}", clone), format!("{:?}", ipld));
+
+        // Unshared values aren't copied.
+        drop(clone);
+        let mut unshared = shared;
+        let pointer: *const Ipld = &*unshared;
+        unshared
+            .make_mut()
+            .insert("name", crate::ipld!("unshared"))
+            .unwrap();
+        assert_eq!(pointer, &*unshared as *const Ipld);
+        assert_eq!(
+            Ipld::from(unshared),
+            crate::ipld!({"list": [1, 2], "name": "unshared"})
+        );
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let shared = SharedIpld::new(crate::ipld!([1]));
+        let mut clone = shared.clone();
+        clone.make_mut().push(crate::ipld!(2)).unwrap();
+        assert_eq!(*shared, crate::ipld!([1]));
+        assert_eq!(*clone, crate::ipld!([1, 2]));
+        assert!(shared < clone);
+        assert_eq!(Arc::<Ipld>::from(clone).as_ref(), &crate::ipld!([1, 2]));
+    }
+
+    #[test]
+    fn test_drop_deeply_nested() {
+        use alloc::vec;
+
+        let nested = (0..100_000).fold(Ipld::Null, |ipld, _| Ipld::List(vec![ipld]));
+        let shared = SharedIpld::new(nested);
+        drop(shared.clone());
+        drop(shared);
+    }
+}
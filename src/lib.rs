@@ -6,12 +6,25 @@
 
 extern crate alloc;
 
-#[cfg(all(feature = "std", feature = "codec"))]
+#[cfg(feature = "block")]
+pub mod block;
+pub mod blockstore;
+#[cfg(feature = "car")]
+pub mod car;
+#[cfg(feature = "codec")]
 pub mod codec;
 pub mod convert;
+pub mod diff;
+#[cfg(feature = "hash")]
+pub mod hash;
 pub mod ipld;
+pub mod patch;
+pub mod path;
+pub mod selector;
 #[cfg(feature = "serde")]
 pub mod serde;
+#[cfg(all(feature = "std", feature = "codec"))]
+pub mod traversal;
 
 #[cfg(feature = "arb")]
 mod arb;
@@ -19,6 +32,10 @@ mod macros;
 
 pub use cid;
 
+/// The multihash code of the identity hash function, see `hash::Code::Identity`.
+#[cfg(any(feature = "codec", feature = "hash"))]
+const IDENTITY_HASH_CODE: u64 = 0x00;
+
 // This is a hack to get those types working in the `ipld!` macro with and without `no_std`. The
 // idea is from
 // https://stackoverflow.com/questions/71675411/refer-to-an-extern-crate-in-macro-expansion/71675639#71675639
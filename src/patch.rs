@@ -0,0 +1,605 @@
+//! IPLD Patch.
+//!
+//! This is an implementation of the [IPLD Patch] specification, which is modelled after
+//! [RFC 6902] (JSON Patch). Operations are addressed by [`IpldPath`]s.
+//!
+//! In the IPLD representation of an operation, paths are written in their string representation,
+//! which is the same as of [RFC 6901] JSON Pointers.
+//!
+//! [IPLD Patch]: https://ipld.io/specs/patch/
+//! [RFC 6901]: https://datatracker.ietf.org/doc/html/rfc6901
+//! [RFC 6902]: https://datatracker.ietf.org/doc/html/rfc6902
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+};
+use core::{fmt, mem};
+
+use crate::{
+    ipld::{IndexError, Ipld},
+    path::{IpldPath, PathError},
+};
+
+/// Error when applying or parsing IPLD Patch operations.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum PatchError {
+    /// Error when a path of an operation cannot be followed.
+    Path(PathError),
+    /// Error when the value of a `test` operation doesn't match.
+    TestFailed {
+        /// The path of the value that was tested.
+        path: IpldPath,
+    },
+    /// Error when a value would be moved into one of its own children.
+    MoveIntoChild {
+        /// The path of the value that should be moved.
+        from: IpldPath,
+        /// The path the value should be moved to.
+        path: IpldPath,
+    },
+    /// Error when an operation is not valid, e.g. when it cannot be parsed from IPLD.
+    InvalidOperation(String),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(error) => write!(f, "patch error: {}", error),
+            Self::TestFailed { path } => write!(f, "patch test failed at path `{}`", path),
+            Self::MoveIntoChild { from, path } => write!(
+                f,
+                "cannot move value from `{}` into its own child `{}`",
+                from, path
+            ),
+            Self::InvalidOperation(message) => write!(f, "invalid patch operation: {}", message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PatchError {}
+
+impl From<PathError> for PatchError {
+    fn from(error: PathError) -> Self {
+        Self::Path(error)
+    }
+}
+
+/// A single IPLD Patch operation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchOp {
+    /// Adds a value. Map entries are inserted or replaced, List elements are inserted at the given
+    /// position. The segment `-` appends to a List.
+    Add {
+        /// The path of the value to add.
+        path: IpldPath,
+        /// The value to add.
+        value: Ipld,
+    },
+    /// Removes a value.
+    Remove {
+        /// The path of the value to remove.
+        path: IpldPath,
+    },
+    /// Replaces an existing value.
+    Replace {
+        /// The path of the value to replace.
+        path: IpldPath,
+        /// The new value.
+        value: Ipld,
+    },
+    /// Removes a value and adds it at a different path.
+    Move {
+        /// The path of the value to move.
+        from: IpldPath,
+        /// The path to move the value to.
+        path: IpldPath,
+    },
+    /// Copies a value to a different path.
+    Copy {
+        /// The path of the value to copy.
+        from: IpldPath,
+        /// The path to copy the value to.
+        path: IpldPath,
+    },
+    /// Tests that the value at a path is equal to the given one.
+    Test {
+        /// The path of the value to test.
+        path: IpldPath,
+        /// The expected value.
+        value: Ipld,
+    },
+}
+
+impl PatchOp {
+    /// Applies the operation to the given value.
+    ///
+    /// If the operation fails, the value might be left partially modified. Use [`apply`] in order
+    /// to apply operations atomically.
+    pub fn apply(&self, ipld: &mut Ipld) -> Result<(), PatchError> {
+        match self {
+            Self::Add { path, value } => add(ipld, path, value.clone()),
+            Self::Remove { path } => remove(ipld, path).map(Ipld::drop_iterative),
+            Self::Replace { path, value } => {
+                mem::replace(ipld.get_path_mut(path)?, value.clone()).drop_iterative();
+                Ok(())
+            }
+            Self::Move { from, path } => {
+                if from == path {
+                    return Ok(());
+                }
+                if from.len() < path.len() && path.segments().starts_with(from.segments()) {
+                    return Err(PatchError::MoveIntoChild {
+                        from: from.clone(),
+                        path: path.clone(),
+                    });
+                }
+                let value = remove(ipld, from)?;
+                add(ipld, path, value)
+            }
+            Self::Copy { from, path } => {
+                let value = ipld.get_path(from)?.clone();
+                add(ipld, path, value)
+            }
+            Self::Test { path, value } => {
+                if ipld.get_path(path)? == value {
+                    Ok(())
+                } else {
+                    Err(PatchError::TestFailed { path: path.clone() })
+                }
+            }
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Add { .. } => "add",
+            Self::Remove { .. } => "remove",
+            Self::Replace { .. } => "replace",
+            Self::Move { .. } => "move",
+            Self::Copy { .. } => "copy",
+            Self::Test { .. } => "test",
+        }
+    }
+}
+
+/// Applies a sequence of operations to the given value.
+///
+/// The operations are applied atomically, if one of them fails, the value is left unchanged.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{ipld, patch::{self, PatchOp}};
+///
+/// let mut data = ipld!({"name": "ipld", "tags": ["a"]});
+/// let ops = [
+///     PatchOp::Add { path: "/tags/-".parse().unwrap(), value: ipld!("b") },
+///     PatchOp::Remove { path: "/name".parse().unwrap() },
+/// ];
+/// patch::apply(&mut data, &ops).unwrap();
+/// assert_eq!(data, ipld!({"tags": ["a", "b"]}));
+/// ```
+pub fn apply(ipld: &mut Ipld, ops: &[PatchOp]) -> Result<(), PatchError> {
+    let mut patched = ipld.clone();
+    for op in ops {
+        if let Err(error) = op.apply(&mut patched) {
+            patched.drop_iterative();
+            return Err(error);
+        }
+    }
+    mem::replace(ipld, patched).drop_iterative();
+    Ok(())
+}
+
+/// Converts an error of a single level access into one containing the full path.
+fn index_to_path_error(error: IndexError, path: &IpldPath) -> PathError {
+    let path = path.clone();
+    match error {
+        IndexError::ParseInteger(_) => PathError::ParseInteger { path },
+        IndexError::WrongKind(kind) => PathError::WrongKind { path, kind },
+        _ => PathError::NotFound { path },
+    }
+}
+
+fn add(ipld: &mut Ipld, path: &IpldPath, value: Ipld) -> Result<(), PatchError> {
+    let (parent, last) = match (path.parent(), path.last()) {
+        (Some(parent), Some(last)) => (parent, last),
+        _ => {
+            mem::replace(ipld, value).drop_iterative();
+            return Ok(());
+        }
+    };
+    let target = ipld.get_path_mut(&parent)?;
+    match target {
+        Ipld::List(list) if last == "-" => {
+            list.push(value);
+            Ok(())
+        }
+        _ => {
+            if let Some(replaced) = target
+                .insert(last, value)
+                .map_err(|error| index_to_path_error(error, path))?
+            {
+                replaced.drop_iterative();
+            }
+            Ok(())
+        }
+    }
+}
+
+fn remove(ipld: &mut Ipld, path: &IpldPath) -> Result<Ipld, PatchError> {
+    let (parent, last) = match (path.parent(), path.last()) {
+        (Some(parent), Some(last)) => (parent, last),
+        _ => {
+            return Err(PatchError::InvalidOperation(
+                "the root cannot be removed".to_string(),
+            ))
+        }
+    };
+    ipld.get_path_mut(&parent)?
+        .remove(last)
+        .map_err(|error| index_to_path_error(error, path))?
+        .ok_or_else(|| PathError::NotFound { path: path.clone() }.into())
+}
+
+fn string_field(map: &mut BTreeMap<String, Ipld>, field: &str) -> Result<String, PatchError> {
+    match map.remove(field) {
+        Some(Ipld::String(value)) => Ok(value),
+        _ => Err(PatchError::InvalidOperation(format!(
+            "field `{}` must be a string",
+            field
+        ))),
+    }
+}
+
+impl From<PatchOp> for Ipld {
+    fn from(op: PatchOp) -> Self {
+        let mut map = BTreeMap::new();
+        map.insert("op".to_string(), Ipld::String(op.name().to_string()));
+        match op {
+            PatchOp::Add { path, value }
+            | PatchOp::Replace { path, value }
+            | PatchOp::Test { path, value } => {
+                map.insert("path".to_string(), Ipld::String(path.to_string()));
+                map.insert("value".to_string(), value);
+            }
+            PatchOp::Remove { path } => {
+                map.insert("path".to_string(), Ipld::String(path.to_string()));
+            }
+            PatchOp::Move { from, path } | PatchOp::Copy { from, path } => {
+                map.insert("from".to_string(), Ipld::String(from.to_string()));
+                map.insert("path".to_string(), Ipld::String(path.to_string()));
+            }
+        }
+        Ipld::Map(map)
+    }
+}
+
+impl TryFrom<Ipld> for PatchOp {
+    type Error = PatchError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        let mut map = match ipld {
+            Ipld::Map(map) => map,
+            other => {
+                return Err(PatchError::InvalidOperation(format!(
+                    "expected IPLD Map but found {:?}",
+                    other.kind()
+                )))
+            }
+        };
+        let op = string_field(&mut map, "op")?;
+        let path = string_field(&mut map, "path")?.parse::<IpldPath>()?;
+        let from = match op.as_str() {
+            "move" | "copy" => Some(string_field(&mut map, "from")?.parse::<IpldPath>()?),
+            _ => None,
+        };
+        let value = map.remove("value");
+        let missing_value =
+            || PatchError::InvalidOperation(format!("operation `{}` needs a `value`", op));
+        match (op.as_str(), from) {
+            ("add", _) => Ok(Self::Add {
+                path,
+                value: value.ok_or_else(missing_value)?,
+            }),
+            ("remove", _) => Ok(Self::Remove { path }),
+            ("replace", _) => Ok(Self::Replace {
+                path,
+                value: value.ok_or_else(missing_value)?,
+            }),
+            ("move", Some(from)) => Ok(Self::Move { from, path }),
+            ("copy", Some(from)) => Ok(Self::Copy { from, path }),
+            ("test", _) => Ok(Self::Test {
+                path,
+                value: value.ok_or_else(missing_value)?,
+            }),
+            _ => Err(PatchError::InvalidOperation(format!(
+                "unknown operation `{}`",
+                op
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for PatchOp {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        // The fields are in the order DAG-CBOR sorts Map keys in: by length, then bytewise.
+        let (from, path, value) = match self {
+            Self::Add { path, value }
+            | Self::Replace { path, value }
+            | Self::Test { path, value } => (None, path, Some(value)),
+            Self::Remove { path } => (None, path, None),
+            Self::Move { from, path } | Self::Copy { from, path } => (Some(from), path, None),
+        };
+        let len = 2 + usize::from(from.is_some()) + usize::from(value.is_some());
+        let mut map = serializer.serialize_map(Some(len))?;
+        map.serialize_entry("op", self.name())?;
+        if let Some(from) = from {
+            map.serialize_entry("from", &from.to_string())?;
+        }
+        map.serialize_entry("path", &path.to_string())?;
+        if let Some(value) = value {
+            map.serialize_entry("value", value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PatchOp {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let ipld = Ipld::deserialize(deserializer)?;
+        Self::try_from(ipld).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+    use crate::ipld;
+
+    fn path(path: &str) -> IpldPath {
+        path.parse().unwrap()
+    }
+
+    #[test]
+    fn test_add() {
+        let mut ipld = ipld!({"list": [0, 2]});
+        let ops = [
+            PatchOp::Add {
+                path: path("/list/1"),
+                value: ipld!(1),
+            },
+            PatchOp::Add {
+                path: path("/list/-"),
+                value: ipld!(3),
+            },
+            PatchOp::Add {
+                path: path("/map"),
+                value: ipld!({}),
+            },
+            PatchOp::Add {
+                path: path("/map/a"),
+                value: ipld!(true),
+            },
+        ];
+        apply(&mut ipld, &ops).unwrap();
+        assert_eq!(ipld, ipld!({"list": [0, 1, 2, 3], "map": {"a": true}}));
+
+        let op = PatchOp::Add {
+            path: path("/list/9"),
+            value: ipld!(9),
+        };
+        assert!(matches!(
+            op.apply(&mut ipld),
+            Err(PatchError::Path(PathError::NotFound { .. }))
+        ));
+        let op = PatchOp::Add {
+            path: path("/missing/a"),
+            value: ipld!(9),
+        };
+        assert!(matches!(
+            op.apply(&mut ipld),
+            Err(PatchError::Path(PathError::NotFound { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_remove_replace() {
+        let mut ipld = ipld!({"list": [0, 1, 2], "a": "b"});
+        let ops = [
+            PatchOp::Remove {
+                path: path("/list/0"),
+            },
+            PatchOp::Replace {
+                path: path("/a"),
+                value: ipld!("c"),
+            },
+        ];
+        apply(&mut ipld, &ops).unwrap();
+        assert_eq!(ipld, ipld!({"list": [1, 2], "a": "c"}));
+
+        let op = PatchOp::Remove { path: path("/b") };
+        assert!(op.apply(&mut ipld).is_err());
+        let op = PatchOp::Replace {
+            path: path("/b"),
+            value: ipld!(null),
+        };
+        assert!(op.apply(&mut ipld).is_err());
+    }
+
+    #[test]
+    fn test_non_canonical_index() {
+        let mut ipld = ipld!({"list": [0, 1, 2]});
+        for index in ["01", "+1"] {
+            let ops = [
+                PatchOp::Add {
+                    path: path("/list").join(index),
+                    value: ipld!(9),
+                },
+                PatchOp::Remove {
+                    path: path("/list").join(index),
+                },
+            ];
+            for op in ops {
+                assert!(matches!(
+                    op.apply(&mut ipld),
+                    Err(PatchError::Path(PathError::ParseInteger { .. }))
+                ));
+            }
+        }
+        assert_eq!(ipld, ipld!({"list": [0, 1, 2]}));
+    }
+
+    #[test]
+    fn test_move_copy() {
+        let mut ipld = ipld!({"a": {"b": 1}, "list": [0]});
+        let ops = [
+            PatchOp::Copy {
+                from: path("/a/b"),
+                path: path("/list/-"),
+            },
+            PatchOp::Move {
+                from: path("/a"),
+                path: path("/c"),
+            },
+        ];
+        apply(&mut ipld, &ops).unwrap();
+        assert_eq!(ipld, ipld!({"c": {"b": 1}, "list": [0, 1]}));
+
+        let op = PatchOp::Move {
+            from: path("/c"),
+            path: path("/c/d"),
+        };
+        assert!(matches!(
+            op.apply(&mut ipld),
+            Err(PatchError::MoveIntoChild { .. })
+        ));
+    }
+
+    #[test]
+    fn test_atomic() {
+        let mut ipld = ipld!({"a": 1});
+        let ops = [
+            PatchOp::Remove { path: path("/a") },
+            PatchOp::Test {
+                path: path("/b"),
+                value: ipld!(1),
+            },
+        ];
+        assert!(apply(&mut ipld, &ops).is_err());
+        assert_eq!(ipld, ipld!({"a": 1}));
+
+        let op = PatchOp::Test {
+            path: path("/a"),
+            value: ipld!(2),
+        };
+        assert!(matches!(
+            op.apply(&mut ipld),
+            Err(PatchError::TestFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ipld_roundtrip() {
+        let ops = vec![
+            PatchOp::Add {
+                path: path("/a~1b/0"),
+                value: ipld!([1]),
+            },
+            PatchOp::Remove { path: path("/a") },
+            PatchOp::Replace {
+                path: IpldPath::new(),
+                value: ipld!(null),
+            },
+            PatchOp::Move {
+                from: path("/a"),
+                path: path("/b"),
+            },
+            PatchOp::Copy {
+                from: path("/a"),
+                path: path("/b"),
+            },
+            PatchOp::Test {
+                path: path("/a"),
+                value: ipld!("a"),
+            },
+        ];
+        let ipld: Vec<Ipld> = ops.iter().cloned().map(Ipld::from).collect();
+        assert_eq!(
+            ipld[0],
+            ipld!({"op": "add", "path": "/a~1b/0", "value": [1]})
+        );
+        let decoded = ipld
+            .into_iter()
+            .map(PatchOp::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(decoded, ops);
+
+        // The empty key is distinct from the root.
+        let op = PatchOp::Remove {
+            path: IpldPath::from_iter([""]),
+        };
+        let ipld = Ipld::from(op.clone());
+        assert_eq!(ipld, ipld!({"op": "remove", "path": "/"}));
+        assert_eq!(PatchOp::try_from(ipld).unwrap(), op);
+        assert_eq!(
+            PatchOp::try_from(ipld!({"op": "remove", "path": ""})).unwrap(),
+            PatchOp::Remove {
+                path: IpldPath::new()
+            }
+        );
+
+        assert!(PatchOp::try_from(ipld!({"op": "remove", "path": "a"})).is_err());
+        assert!(PatchOp::try_from(ipld!({"op": "add", "path": "/a"})).is_err());
+        assert!(PatchOp::try_from(ipld!({"op": "nope", "path": "/a"})).is_err());
+        assert!(PatchOp::try_from(ipld!({"op": "move", "path": "/a"})).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        use crate::serde::{from_ipld, to_ipld};
+
+        let op = PatchOp::Move {
+            from: path("/a"),
+            path: path("/b/0"),
+        };
+        let ipld = to_ipld(&op).unwrap();
+        assert_eq!(ipld, ipld!({"op": "move", "from": "/a", "path": "/b/0"}));
+        let decoded: PatchOp = from_ipld(ipld).unwrap();
+        assert_eq!(decoded, op);
+
+        let ops = [
+            PatchOp::Add {
+                path: IpldPath::from_iter([""]),
+                value: ipld!({"a": [1]}),
+            },
+            PatchOp::Remove {
+                path: IpldPath::new(),
+            },
+            PatchOp::Copy {
+                from: path("/a"),
+                path: path("/b"),
+            },
+        ];
+        for op in ops {
+            assert_eq!(to_ipld(&op).unwrap(), Ipld::from(op.clone()));
+        }
+    }
+}
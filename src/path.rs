@@ -0,0 +1,535 @@
+//! IPLD Paths.
+//!
+//! An [`IpldPath`] is a sequence of segments, which address a value nested within Lists and Maps.
+//! Segments that are used on a List are parsed as an integer index, segments used on a Map are
+//! used as key.
+//!
+//! The string representation is the same as of [RFC 6901] (JSON Pointer): the root is the empty
+//! string and every segment is prefixed with a slash, e.g. `/a/b/0/c`, hence `/` is the path to
+//! the empty key. In order to be able to address Map keys that contain a slash, segments are
+//! escaped: `~` is written as `~0` and `/` as `~1`.
+//!
+//! [RFC 6901]: https://datatracker.ietf.org/doc/html/rfc6901
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, mem, str::FromStr};
+
+use crate::ipld::{Ipld, IpldIndex, IpldKind};
+
+/// Error when parsing an IPLD Path or when using it to access IPLD values.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum PathError {
+    /// Error when a segment contains an invalid escape sequence.
+    InvalidEscape(String),
+    /// Error when a non-empty path doesn't start with a slash.
+    MissingLeadingSlash(String),
+    /// Error when there is no value at the given path.
+    NotFound {
+        /// The path up to and including the segment that wasn't found.
+        path: IpldPath,
+    },
+    /// Error when a segment used on an IPLD List cannot be parsed into an integer.
+    ParseInteger {
+        /// The path up to and including the segment that isn't an integer.
+        path: IpldPath,
+    },
+    /// Error when a segment is used on a value that isn't an IPLD List or Map.
+    WrongKind {
+        /// The path up to and including the segment that was used.
+        path: IpldPath,
+        /// The kind of the value the segment was used on.
+        kind: IpldKind,
+    },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEscape(segment) => {
+                write!(f, "invalid escape sequence in path segment: {}", segment)
+            }
+            Self::MissingLeadingSlash(path) => {
+                write!(f, "path `{}` must be empty or start with `/`", path)
+            }
+            Self::NotFound { path } => write!(f, "no value found at path `{}`", path),
+            Self::ParseInteger { path } => {
+                write!(
+                    f,
+                    "cannot parse last segment into integer at path `{}`",
+                    path
+                )
+            }
+            Self::WrongKind { path, kind } => write!(
+                f,
+                "expected IPLD List or Map but found {:?} at path `{}`",
+                kind, path
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+/// A path into nested IPLD Lists and Maps.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{ipld, path::IpldPath};
+///
+/// let data = ipld!({"a": {"b": [{"c": true}]}});
+/// let path: IpldPath = "/a/b/0/c".parse().unwrap();
+/// assert_eq!(data.get_path(&path).unwrap(), &ipld!(true));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IpldPath(Vec<String>);
+
+impl IpldPath {
+    /// Returns an empty path, which points to the root of a value.
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Returns the (unescaped) segments of the path.
+    pub fn segments(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Returns the number of segments.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the path has no segments, i.e. it points to the root of a value.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the last segment of the path.
+    pub fn last(&self) -> Option<&str> {
+        self.0.last().map(String::as_str)
+    }
+
+    /// Appends a segment to the end of the path.
+    pub fn push<'a, T: Into<IpldIndex<'a>>>(&mut self, segment: T) {
+        self.0.push(String::from(segment.into()))
+    }
+
+    /// Removes the last segment of the path and returns it.
+    pub fn pop(&mut self) -> Option<String> {
+        self.0.pop()
+    }
+
+    /// Returns a new path with the given segment appended.
+    pub fn join<'a, T: Into<IpldIndex<'a>>>(&self, segment: T) -> Self {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+
+    /// Returns the path without its last segment, or `None` if the path is empty.
+    pub fn parent(&self) -> Option<Self> {
+        self.0.split_last().map(|(_, parent)| Self(parent.to_vec()))
+    }
+
+    /// Returns the first `len` segments as a new path.
+    pub(crate) fn prefix(&self, len: usize) -> Self {
+        Self(self.0[..len].to_vec())
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for IpldPath {
+    fn from_iter<I: IntoIterator<Item = S>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Parses a path, the empty string is the root, all other paths must start with a slash.
+impl FromStr for IpldPath {
+    type Err = PathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        if path.is_empty() {
+            return Ok(Self::new());
+        }
+        match path.strip_prefix('/') {
+            Some(segments) => segments.split('/').map(unescape).collect(),
+            None => Err(PathError::MissingLeadingSlash(path.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for IpldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.0 {
+            f.write_str("/")?;
+            for ch in segment.chars() {
+                match ch {
+                    '~' => f.write_str("~0")?,
+                    '/' => f.write_str("~1")?,
+                    _ => write!(f, "{}", ch)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn unescape(segment: &str) -> Result<String, PathError> {
+    if !segment.contains('~') {
+        return Ok(segment.to_string());
+    }
+    let mut unescaped = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '~' {
+            match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => return Err(PathError::InvalidEscape(segment.to_string())),
+            }
+        } else {
+            unescaped.push(ch);
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Parses a string into a List index.
+///
+/// Only the canonical decimal representation is accepted, i.e. no sign and no leading zeros, so
+/// that every List element is addressed by exactly one string.
+pub(crate) fn parse_list_index(index: &str) -> Option<usize> {
+    let canonical = index.bytes().all(|byte| byte.is_ascii_digit())
+        && (index == "0" || !index.starts_with('0'));
+    index.parse().ok().filter(|_| canonical)
+}
+
+/// Parses the segment at the given position of the path into a List index.
+fn list_index(path: &IpldPath, position: usize) -> Result<usize, PathError> {
+    parse_list_index(&path.0[position]).ok_or_else(|| PathError::ParseInteger {
+        path: path.prefix(position + 1),
+    })
+}
+
+impl Ipld {
+    /// Returns the value at the given path.
+    ///
+    /// In case the path cannot be followed, the error contains the path up to and including the
+    /// segment that failed.
+    pub fn get_path(&self, path: &IpldPath) -> Result<&Self, PathError> {
+        let mut current = self;
+        for (position, segment) in path.0.iter().enumerate() {
+            current = match current {
+                Ipld::List(list) => list.get(list_index(path, position)?),
+                Ipld::Map(map) => map.get(segment),
+                other => {
+                    return Err(PathError::WrongKind {
+                        path: path.prefix(position + 1),
+                        kind: other.kind(),
+                    })
+                }
+            }
+            .ok_or_else(|| PathError::NotFound {
+                path: path.prefix(position + 1),
+            })?;
+        }
+        Ok(current)
+    }
+
+    /// Returns a mutable reference to the value at the given path.
+    ///
+    /// In case the path cannot be followed, the error contains the path up to and including the
+    /// segment that failed.
+    pub fn get_path_mut(&mut self, path: &IpldPath) -> Result<&mut Self, PathError> {
+        let mut current = self;
+        for (position, segment) in path.0.iter().enumerate() {
+            current = match current {
+                Ipld::List(list) => list.get_mut(list_index(path, position)?),
+                Ipld::Map(map) => map.get_mut(segment),
+                other => {
+                    return Err(PathError::WrongKind {
+                        path: path.prefix(position + 1),
+                        kind: other.kind(),
+                    })
+                }
+            }
+            .ok_or_else(|| PathError::NotFound {
+                path: path.prefix(position + 1),
+            })?;
+        }
+        Ok(current)
+    }
+
+    /// Sets the value at the given path and returns the previous one.
+    ///
+    /// Missing intermediate Map entries are created as empty Maps. If the last segment is used on
+    /// a List, it either replaces an existing element or, if it equals the length of the List,
+    /// appends the value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ipld_core::{ipld, path::IpldPath};
+    ///
+    /// let mut data = ipld!({});
+    /// let path: IpldPath = "/a/b/c".parse().unwrap();
+    /// data.set_path(&path, ipld!([1, 2])).unwrap();
+    /// assert_eq!(data, ipld!({"a": {"b": {"c": [1, 2]}}}));
+    /// ```
+    pub fn set_path(&mut self, path: &IpldPath, value: Self) -> Result<Option<Self>, PathError> {
+        let (last, parents) = match path.0.split_last() {
+            Some(split) => split,
+            None => return Ok(Some(mem::replace(self, value))),
+        };
+
+        let mut current = self;
+        for (position, segment) in parents.iter().enumerate() {
+            current = match current {
+                Ipld::List(list) => list.get_mut(list_index(path, position)?).ok_or_else(|| {
+                    PathError::NotFound {
+                        path: path.prefix(position + 1),
+                    }
+                })?,
+                Ipld::Map(map) => map
+                    .entry(segment.clone())
+                    .or_insert_with(|| Ipld::Map(BTreeMap::new())),
+                other => {
+                    return Err(PathError::WrongKind {
+                        path: path.prefix(position + 1),
+                        kind: other.kind(),
+                    })
+                }
+            };
+        }
+
+        match current {
+            Ipld::List(list) => {
+                let index = list_index(path, parents.len())?;
+                if index < list.len() {
+                    Ok(Some(mem::replace(&mut list[index], value)))
+                } else if index == list.len() {
+                    list.push(value);
+                    Ok(None)
+                } else {
+                    Err(PathError::NotFound { path: path.clone() })
+                }
+            }
+            Ipld::Map(map) => Ok(map.insert(last.clone(), value)),
+            other => Err(PathError::WrongKind {
+                path: path.clone(),
+                kind: other.kind(),
+            }),
+        }
+    }
+
+    /// Destructs the value and returns the value at the given path.
+    ///
+    /// In case the path cannot be followed, the error contains the path up to and including the
+    /// segment that failed.
+    pub fn take_path(self, path: &IpldPath) -> Result<Self, PathError> {
+        let mut current = self;
+        for (position, segment) in path.0.iter().enumerate() {
+            // The rest of the value is dropped without recursing, it may be deeply nested.
+            current = match current {
+                Ipld::List(mut list) => {
+                    let taken = match list_index(path, position) {
+                        Ok(index) if index < list.len() => Ok(Some(list.swap_remove(index))),
+                        Ok(_) => Ok(None),
+                        Err(error) => Err(error),
+                    };
+                    Ipld::List(list).drop_iterative();
+                    taken?
+                }
+                Ipld::Map(mut map) => {
+                    let taken = map.remove(segment);
+                    Ipld::Map(map).drop_iterative();
+                    taken
+                }
+                other => {
+                    return Err(PathError::WrongKind {
+                        path: path.prefix(position + 1),
+                        kind: other.kind(),
+                    })
+                }
+            }
+            .ok_or_else(|| PathError::NotFound {
+                path: path.prefix(position + 1),
+            })?;
+        }
+        Ok(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use super::*;
+    use crate::ipld;
+
+    #[test]
+    fn test_parse_display() {
+        let path: IpldPath = "/a/b/0/c".parse().unwrap();
+        assert_eq!(path.segments(), ["a", "b", "0", "c"]);
+        assert_eq!(path.to_string(), "/a/b/0/c");
+
+        assert!("".parse::<IpldPath>().unwrap().is_empty());
+        assert_eq!(IpldPath::new().to_string(), "");
+        assert_eq!("/".parse::<IpldPath>().unwrap().segments(), [""]);
+
+        assert!(matches!(
+            "a/b".parse::<IpldPath>(),
+            Err(PathError::MissingLeadingSlash(path)) if path == "a/b"
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let paths = [
+            IpldPath::new(),
+            IpldPath::from_iter([""]),
+            IpldPath::from_iter(["", ""]),
+            IpldPath::from_iter(["", "a"]),
+            IpldPath::from_iter(["a", ""]),
+            IpldPath::from_iter(["a", "", "b"]),
+            IpldPath::from_iter(["a/b", "~", "~1", "0"]),
+        ];
+        for path in paths {
+            assert_eq!(path.to_string().parse::<IpldPath>().unwrap(), path);
+        }
+
+        // An empty key is not confused with the root.
+        let ipld = ipld!({"": {"x": 1}, "x": 2});
+        let path: IpldPath = IpldPath::from_iter(["", "x"]).to_string().parse().unwrap();
+        assert_eq!(ipld.get_path(&path).unwrap(), &ipld!(1));
+    }
+
+    #[test]
+    fn test_escape() {
+        let path: IpldPath = "/a~1b/c~0d/~01".parse().unwrap();
+        assert_eq!(path.segments(), ["a/b", "c~d", "~1"]);
+        assert_eq!(path.to_string(), "/a~1b/c~0d/~01");
+
+        assert!(matches!(
+            "/a/b~2".parse::<IpldPath>(),
+            Err(PathError::InvalidEscape(segment)) if segment == "b~2"
+        ));
+        assert!("/a~".parse::<IpldPath>().is_err());
+    }
+
+    #[test]
+    fn test_join_parent() {
+        let path = IpldPath::new().join("a").join(3).join("b/c".to_string());
+        assert_eq!(path.segments(), ["a", "3", "b/c"]);
+        assert_eq!(path.last(), Some("b/c"));
+        assert_eq!(path.to_string(), "/a/3/b~1c");
+
+        let parent = path.parent().unwrap();
+        assert_eq!(parent.segments(), ["a", "3"]);
+        assert_eq!(IpldPath::new().parent(), None);
+    }
+
+    #[test]
+    fn test_get_path() {
+        let ipld = ipld!({"a": {"b": [0, {"c": true}]}});
+        let path: IpldPath = "/a/b/1/c".parse().unwrap();
+        assert_eq!(ipld.get_path(&path).unwrap(), &Ipld::Bool(true));
+        assert_eq!(ipld.get_path(&IpldPath::new()).unwrap(), &ipld);
+
+        let path: IpldPath = "/a/b/2/c".parse().unwrap();
+        assert!(matches!(
+            ipld.get_path(&path),
+            Err(PathError::NotFound { path }) if path.to_string() == "/a/b/2"
+        ));
+
+        let path: IpldPath = "/a/b/x".parse().unwrap();
+        assert!(matches!(
+            ipld.get_path(&path),
+            Err(PathError::ParseInteger { path }) if path.to_string() == "/a/b/x"
+        ));
+
+        for index in ["+1", "01", "-0", ""] {
+            let path = IpldPath::from_iter(["a", "b", index]);
+            assert!(matches!(
+                ipld.get_path(&path),
+                Err(PathError::ParseInteger { .. })
+            ));
+        }
+
+        let path: IpldPath = "/a/b/0/d".parse().unwrap();
+        assert!(matches!(
+            ipld.get_path(&path),
+            Err(PathError::WrongKind { path, kind: IpldKind::Integer })
+                if path.to_string() == "/a/b/0/d"
+        ));
+    }
+
+    #[test]
+    fn test_get_path_mut() {
+        let mut ipld = ipld!({"a": [1, 2]});
+        let path: IpldPath = "/a/1".parse().unwrap();
+        *ipld.get_path_mut(&path).unwrap() = Ipld::Integer(3);
+        assert_eq!(ipld, ipld!({"a": [1, 3]}));
+    }
+
+    #[test]
+    fn test_set_path() {
+        let mut ipld = ipld!({"a": [0, 1]});
+        let path: IpldPath = "/a/1".parse().unwrap();
+        assert_eq!(
+            ipld.set_path(&path, Ipld::Integer(2)).unwrap(),
+            Some(Ipld::Integer(1))
+        );
+        let path: IpldPath = "/a/2".parse().unwrap();
+        assert_eq!(ipld.set_path(&path, Ipld::Integer(3)).unwrap(), None);
+        let path: IpldPath = "/b/c".parse().unwrap();
+        assert_eq!(ipld.set_path(&path, Ipld::Bool(true)).unwrap(), None);
+        assert_eq!(ipld, ipld!({"a": [0, 2, 3], "b": {"c": true}}));
+
+        let path: IpldPath = "/a/5".parse().unwrap();
+        assert!(matches!(
+            ipld.set_path(&path, Ipld::Null),
+            Err(PathError::NotFound { path }) if path.to_string() == "/a/5"
+        ));
+        let path: IpldPath = "/b/c/d".parse().unwrap();
+        assert!(matches!(
+            ipld.set_path(&path, Ipld::Null),
+            Err(PathError::WrongKind {
+                kind: IpldKind::Bool,
+                ..
+            })
+        ));
+
+        let mut ipld = Ipld::Null;
+        assert_eq!(
+            ipld.set_path(&IpldPath::new(), Ipld::Integer(1)).unwrap(),
+            Some(Ipld::Null)
+        );
+        assert_eq!(ipld, Ipld::Integer(1));
+    }
+
+    #[test]
+    fn test_take_path() {
+        let ipld = ipld!({"a": {"b": [0, {"c": "d"}]}});
+        let path: IpldPath = "/a/b/1".parse().unwrap();
+        assert_eq!(ipld.clone().take_path(&path).unwrap(), ipld!({"c": "d"}));
+
+        let path: IpldPath = "/a/c".parse().unwrap();
+        assert!(matches!(
+            ipld.take_path(&path),
+            Err(PathError::NotFound { path }) if path.segments() == vec!["a", "c"]
+        ));
+
+        // The rest of the value is dropped without overflowing the stack.
+        let nested = (0..100_000).fold(Ipld::Null, |ipld, _| Ipld::List(vec![ipld]));
+        let ipld = Ipld::List(vec![ipld!("taken"), nested]);
+        let path: IpldPath = "/0".parse().unwrap();
+        assert_eq!(ipld.take_path(&path).unwrap(), ipld!("taken"));
+    }
+}
@@ -0,0 +1,910 @@
+//! IPLD Selectors.
+//!
+//! This is an implementation of the [IPLD Selectors] specification. Selectors are IPLD values
+//! themselves, they can be parsed from [`Ipld`] via `TryFrom` or, with the `serde` feature
+//! enabled, directly be deserialized from any IPLD codec.
+//!
+//! Evaluating a selector with [`Selector::select`] works on a single in-memory [`Ipld`] value,
+//! links are not followed.
+//!
+//! [IPLD Selectors]: https://ipld.io/specs/selectors/
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{
+    ipld::{Ipld, IpldIndex, IpldKind},
+    path::IpldPath,
+};
+
+/// The maximum nesting depth of Lists and Maps in the IPLD representation of a selector, it's far
+/// more than any practical selector needs.
+const MAX_DEPTH: usize = 128;
+
+/// Error when parsing a selector.
+#[derive(Clone, Debug)]
+pub struct SelectorError(String);
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid selector: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SelectorError {}
+
+/// An IPLD Selector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Selector {
+    /// Marks the current node as matched.
+    Matcher,
+    /// Applies the next selector to all elements of a List or all values of a Map.
+    ExploreAll {
+        /// The selector applied to the children.
+        next: Box<Selector>,
+    },
+    /// Applies selectors to certain fields of a Map.
+    ExploreFields {
+        /// The field names and the selectors applied to them.
+        fields: BTreeMap<String, Selector>,
+    },
+    /// Applies the next selector to a single element of a List.
+    ExploreIndex {
+        /// The index of the element.
+        index: usize,
+        /// The selector applied to the element.
+        next: Box<Selector>,
+    },
+    /// Applies the next selector to a range of elements of a List.
+    ExploreRange {
+        /// The first index of the range (inclusive).
+        start: usize,
+        /// The last index of the range (exclusive).
+        end: usize,
+        /// The selector applied to the elements.
+        next: Box<Selector>,
+    },
+    /// Applies a sequence repeatedly, the sequence continues wherever it contains an
+    /// [`Selector::ExploreRecursiveEdge`].
+    ExploreRecursive {
+        /// How often the sequence may be applied.
+        limit: RecursionLimit,
+        /// The selector that is applied repeatedly.
+        sequence: Box<Selector>,
+        /// Stops the recursion at nodes that match this condition.
+        stop_at: Option<Condition>,
+    },
+    /// Marks the point where the sequence of the enclosing [`Selector::ExploreRecursive`]
+    /// continues.
+    ExploreRecursiveEdge,
+    /// Applies several selectors to the same node.
+    ExploreUnion(Vec<Selector>),
+    /// Applies the next selector only if the current node matches the condition.
+    ExploreConditional {
+        /// The condition the current node needs to match.
+        condition: Condition,
+        /// The selector applied to the current node.
+        next: Box<Selector>,
+    },
+}
+
+/// The limit of an [`Selector::ExploreRecursive`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecursionLimit {
+    /// There is no limit.
+    None,
+    /// The maximum number of times the sequence is applied.
+    Depth(u64),
+}
+
+/// A condition used by [`Selector::ExploreConditional`] and [`Selector::ExploreRecursive`].
+///
+/// The IPLD Selector specification doesn't define the representation of conditions in detail
+/// yet, the keys used are `hasField`, `=`, `%`, `/`, `and` and `or`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// Matches Maps that contain the given field.
+    HasField(String),
+    /// Matches nodes equal to the given value.
+    HasValue(Ipld),
+    /// Matches nodes of the given kind.
+    HasKind(IpldKind),
+    /// Matches links.
+    IsLink,
+    /// Matches if all conditions match.
+    And(Vec<Condition>),
+    /// Matches if any of the conditions matches.
+    Or(Vec<Condition>),
+}
+
+impl Condition {
+    /// Returns whether the given node matches the condition.
+    pub fn matches(&self, node: &Ipld) -> bool {
+        match self {
+            Self::HasField(field) => matches!(node, Ipld::Map(map) if map.contains_key(field)),
+            Self::HasValue(value) => node == value,
+            Self::HasKind(kind) => &node.kind() == kind,
+            Self::IsLink => matches!(node, Ipld::Link(_)),
+            Self::And(conditions) => conditions.iter().all(|condition| condition.matches(node)),
+            Self::Or(conditions) => conditions.iter().any(|condition| condition.matches(node)),
+        }
+    }
+}
+
+/// The state of an [`Selector::ExploreRecursive`] while the selector is evaluated.
+#[derive(Clone, Copy, Debug)]
+struct Recursion<'s> {
+    sequence: &'s Selector,
+    /// How often the sequence may still be applied, `None` means there's no limit.
+    remaining: Option<u64>,
+    stop_at: Option<&'s Condition>,
+}
+
+/// The selectors that apply to a child node, before the node itself was inspected.
+///
+/// This allows resolving the child first, e.g. loading the block a link points to, as conditions
+/// are evaluated against the resolved node.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PendingState<'s>(Vec<(&'s Selector, Option<Recursion<'s>>)>);
+
+impl<'s> PendingState<'s> {
+    /// Returns the pending state for applying the selector to a root node.
+    pub(crate) fn new(selector: &'s Selector) -> Self {
+        Self(vec![(selector, None)])
+    }
+
+    /// Returns whether no selector applies to the node.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the state of the selectors applied to the given node.
+    pub(crate) fn resolve(self, node: &Ipld) -> SelectorState<'s> {
+        let mut state = SelectorState::default();
+        for (selector, recursion) in self.0 {
+            let stopped = matches!(
+                recursion.and_then(|recursion| recursion.stop_at),
+                Some(condition) if condition.matches(node)
+            );
+            if !stopped {
+                state.expand(selector, recursion, node, false);
+            }
+        }
+        state
+    }
+}
+
+/// The selectors that apply to a certain node while a selector is evaluated.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SelectorState<'s> {
+    /// Selectors that explore children, together with their innermost recursion.
+    explorers: Vec<(&'s Selector, Option<Recursion<'s>>)>,
+    matched: bool,
+}
+
+impl<'s> SelectorState<'s> {
+    /// Returns whether the node is matched.
+    pub(crate) fn is_match(&self) -> bool {
+        self.matched
+    }
+
+    /// Returns whether the children of the node need to be explored.
+    pub(crate) fn is_exploring(&self) -> bool {
+        !self.explorers.is_empty()
+    }
+
+    /// Returns the selectors that apply to the child at the given segment.
+    pub(crate) fn explore(&self, segment: &IpldIndex) -> PendingState<'s> {
+        let mut pending = PendingState::default();
+        for (selector, recursion) in &self.explorers {
+            let next = match (selector, segment) {
+                (Selector::ExploreAll { next }, _) => Some(&**next),
+                (Selector::ExploreFields { fields }, IpldIndex::List(index)) => {
+                    fields.get(&index.to_string())
+                }
+                (Selector::ExploreFields { fields }, IpldIndex::Map(key)) => fields.get(key),
+                (Selector::ExploreFields { fields }, IpldIndex::MapRef(key)) => fields.get(*key),
+                (Selector::ExploreIndex { index, next }, IpldIndex::List(position))
+                    if index == position =>
+                {
+                    Some(&**next)
+                }
+                (Selector::ExploreRange { start, end, next }, IpldIndex::List(position))
+                    if start <= position && position < end =>
+                {
+                    Some(&**next)
+                }
+                _ => None,
+            };
+            // An edge of an exhausted recursion doesn't apply to any node, skip it, so that e.g.
+            // no block is loaded for it.
+            let exhausted = matches!(next, Some(Selector::ExploreRecursiveEdge))
+                && matches!(
+                    recursion.and_then(|recursion| recursion.remaining),
+                    Some(remaining) if remaining < 2
+                );
+            if let (Some(next), false) = (next, exhausted) {
+                pending.0.push((next, *recursion));
+            }
+        }
+        pending
+    }
+
+    /// Adds a selector that applies to the given node.
+    ///
+    /// Selectors that don't explore children directly are resolved, so that only
+    /// [`Selector::ExploreAll`], [`Selector::ExploreFields`], [`Selector::ExploreIndex`] and
+    /// [`Selector::ExploreRange`] are stored.
+    ///
+    /// The recursion is bounded by the nesting depth of the selector, which is limited when it's
+    /// parsed.
+    fn expand(
+        &mut self,
+        selector: &'s Selector,
+        recursion: Option<Recursion<'s>>,
+        node: &Ipld,
+        via_edge: bool,
+    ) {
+        match selector {
+            Selector::Matcher => self.matched = true,
+            Selector::ExploreAll { .. }
+            | Selector::ExploreFields { .. }
+            | Selector::ExploreIndex { .. }
+            | Selector::ExploreRange { .. } => self.explorers.push((selector, recursion)),
+            Selector::ExploreUnion(selectors) => {
+                for selector in selectors {
+                    self.expand(selector, recursion, node, via_edge);
+                }
+            }
+            Selector::ExploreConditional { condition, next } => {
+                if condition.matches(node) {
+                    self.expand(next, recursion, node, via_edge);
+                }
+            }
+            Selector::ExploreRecursive {
+                limit,
+                sequence,
+                stop_at,
+            } => {
+                let recursion = Recursion {
+                    sequence,
+                    remaining: match limit {
+                        RecursionLimit::None => None,
+                        RecursionLimit::Depth(depth) => Some(*depth),
+                    },
+                    stop_at: stop_at.as_ref(),
+                };
+                self.expand(sequence, Some(recursion), node, false);
+            }
+            Selector::ExploreRecursiveEdge => {
+                // An edge that is reached again without exploring a child would loop forever.
+                if via_edge {
+                    return;
+                }
+                // Edges outside of a recursion are rejected when the selector is parsed.
+                if let Some(recursion) = recursion {
+                    let remaining = match recursion.remaining {
+                        Some(remaining) if remaining < 2 => return,
+                        Some(remaining) => Some(remaining - 1),
+                        None => None,
+                    };
+                    let recursion = Recursion {
+                        remaining,
+                        ..recursion
+                    };
+                    self.expand(recursion.sequence, Some(recursion), node, true);
+                }
+            }
+        }
+    }
+}
+
+impl Selector {
+    /// Evaluates the selector against the given value and returns all matched nodes together
+    /// with their paths.
+    ///
+    /// The nodes are returned in depth-first order, List elements in order and Map entries
+    /// sorted by key.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ipld_core::{ipld, selector::Selector};
+    ///
+    /// let data = ipld!({"name": "root", "children": [{"name": "a"}, {"name": "b"}]});
+    /// // Select the names of all children.
+    /// let selector = Selector::try_from(ipld!({
+    ///     "f": {"f>": {"children": {"a": {">": {"f": {"f>": {"name": {".": {}}}}}}}}}
+    /// }))
+    /// .unwrap();
+    ///
+    /// let selected = selector.select(&data);
+    /// assert_eq!(selected.len(), 2);
+    /// assert_eq!(selected[1].0.to_string(), "/children/1/name");
+    /// assert_eq!(selected[1].1, &ipld!("b"));
+    /// ```
+    pub fn select<'a>(&self, ipld: &'a Ipld) -> Vec<(IpldPath, &'a Ipld)> {
+        let mut selected = Vec::new();
+        // Use an explicit stack instead of recursion, so that deeply nested values cannot overflow
+        // the call stack.
+        let mut stack = vec![(IpldPath::new(), ipld, PendingState::new(self).resolve(ipld))];
+        while let Some((path, node, state)) = stack.pop() {
+            if state.is_exploring() {
+                let children: Vec<_> = match node {
+                    Ipld::List(list) => list
+                        .iter()
+                        .enumerate()
+                        .map(|(index, child)| (IpldIndex::List(index), child))
+                        .collect(),
+                    Ipld::Map(map) => map
+                        .iter()
+                        .map(|(key, child)| (IpldIndex::MapRef(key), child))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                for (segment, child) in children.into_iter().rev() {
+                    let pending = state.explore(&segment);
+                    if pending.is_empty() {
+                        continue;
+                    }
+                    let child_state = pending.resolve(child);
+                    if child_state.is_match() || child_state.is_exploring() {
+                        stack.push((path.join(segment), child, child_state));
+                    }
+                }
+            }
+            if state.is_match() {
+                selected.push((path, node));
+            }
+        }
+        selected
+    }
+}
+
+/// The IPLD Schema names of the kinds, as used in conditions.
+fn kind_name(kind: &IpldKind) -> &'static str {
+    match kind {
+        IpldKind::Null => "null",
+        IpldKind::Bool => "bool",
+        IpldKind::Integer => "int",
+        IpldKind::Float => "float",
+        IpldKind::String => "string",
+        IpldKind::Bytes => "bytes",
+        IpldKind::List => "list",
+        IpldKind::Map => "map",
+        IpldKind::Link => "link",
+    }
+}
+
+fn kind_from_name(name: &str) -> Option<IpldKind> {
+    let kind = match name {
+        "null" => IpldKind::Null,
+        "bool" => IpldKind::Bool,
+        "int" => IpldKind::Integer,
+        "float" => IpldKind::Float,
+        "string" => IpldKind::String,
+        "bytes" => IpldKind::Bytes,
+        "list" => IpldKind::List,
+        "map" => IpldKind::Map,
+        "link" => IpldKind::Link,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn empty_map() -> Ipld {
+    Ipld::Map(BTreeMap::new())
+}
+
+/// Creates a Map with a single entry, which is how keyed unions are represented.
+fn keyed(key: &str, value: Ipld) -> Ipld {
+    Ipld::Map(BTreeMap::from([(key.to_string(), value)]))
+}
+
+impl From<Condition> for Ipld {
+    fn from(condition: Condition) -> Self {
+        match condition {
+            Condition::HasField(field) => keyed("hasField", Ipld::String(field)),
+            Condition::HasValue(value) => keyed("=", value),
+            Condition::HasKind(kind) => keyed("%", Ipld::String(kind_name(&kind).to_string())),
+            Condition::IsLink => keyed("/", empty_map()),
+            Condition::And(conditions) => keyed(
+                "and",
+                Ipld::List(conditions.into_iter().map(Into::into).collect()),
+            ),
+            Condition::Or(conditions) => keyed(
+                "or",
+                Ipld::List(conditions.into_iter().map(Into::into).collect()),
+            ),
+        }
+    }
+}
+
+impl From<Selector> for Ipld {
+    fn from(selector: Selector) -> Self {
+        let map = |entries: Vec<(&str, Ipld)>| {
+            Ipld::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+            )
+        };
+        match selector {
+            Selector::Matcher => keyed(".", empty_map()),
+            Selector::ExploreAll { next } => keyed("a", map(vec![(">", (*next).into())])),
+            Selector::ExploreFields { fields } => keyed(
+                "f",
+                map(vec![(
+                    "f>",
+                    Ipld::Map(
+                        fields
+                            .into_iter()
+                            .map(|(key, value)| (key, value.into()))
+                            .collect(),
+                    ),
+                )]),
+            ),
+            Selector::ExploreIndex { index, next } => {
+                keyed("i", map(vec![("i", index.into()), (">", (*next).into())]))
+            }
+            Selector::ExploreRange { start, end, next } => keyed(
+                "r",
+                map(vec![
+                    ("^", start.into()),
+                    ("$", end.into()),
+                    (">", (*next).into()),
+                ]),
+            ),
+            Selector::ExploreRecursive {
+                limit,
+                sequence,
+                stop_at,
+            } => {
+                let limit = match limit {
+                    RecursionLimit::None => keyed("none", empty_map()),
+                    RecursionLimit::Depth(depth) => keyed("depth", depth.into()),
+                };
+                let mut entries = vec![("l", limit), (":>", (*sequence).into())];
+                if let Some(stop_at) = stop_at {
+                    entries.push(("!", stop_at.into()));
+                }
+                keyed("R", map(entries))
+            }
+            Selector::ExploreRecursiveEdge => keyed("@", empty_map()),
+            Selector::ExploreUnion(selectors) => keyed(
+                "|",
+                Ipld::List(selectors.into_iter().map(Into::into).collect()),
+            ),
+            Selector::ExploreConditional { condition, next } => keyed(
+                "&",
+                map(vec![("&", condition.into()), (">", (*next).into())]),
+            ),
+        }
+    }
+}
+
+/// Splits a keyed union into its single key and value.
+fn union_member(ipld: Ipld) -> Result<(String, Ipld), SelectorError> {
+    match ipld {
+        Ipld::Map(map) if map.len() == 1 => Ok(map.into_iter().next().unwrap()),
+        Ipld::Map(map) => Err(SelectorError(format!(
+            "expected a Map with a single key but found {} keys",
+            map.len()
+        ))),
+        other => Err(SelectorError(format!(
+            "expected a Map with a single key but found {:?}",
+            other.kind()
+        ))),
+    }
+}
+
+fn field(map: &mut BTreeMap<String, Ipld>, name: &str) -> Result<Ipld, SelectorError> {
+    map.remove(name)
+        .ok_or_else(|| SelectorError(format!("missing field `{}`", name)))
+}
+
+fn fields(ipld: Ipld) -> Result<BTreeMap<String, Ipld>, SelectorError> {
+    match ipld {
+        Ipld::Map(map) => Ok(map),
+        other => Err(SelectorError(format!(
+            "expected a Map but found {:?}",
+            other.kind()
+        ))),
+    }
+}
+
+fn integer<T: TryFrom<i128>>(ipld: Ipld) -> Result<T, SelectorError> {
+    match ipld {
+        Ipld::Integer(integer) => T::try_from(integer)
+            .map_err(|_| SelectorError(format!("integer out of range: {}", integer))),
+        other => Err(SelectorError(format!(
+            "expected an Integer but found {:?}",
+            other.kind()
+        ))),
+    }
+}
+
+fn next(
+    map: &mut BTreeMap<String, Ipld>,
+    in_recursion: bool,
+) -> Result<Box<Selector>, SelectorError> {
+    Ok(Box::new(parse_selector(field(map, ">")?, in_recursion)?))
+}
+
+fn parse_selector(ipld: Ipld, in_recursion: bool) -> Result<Selector, SelectorError> {
+    let (key, value) = union_member(ipld)?;
+    let selector = match key.as_str() {
+        "." => Selector::Matcher,
+        "a" => Selector::ExploreAll {
+            next: next(&mut fields(value)?, in_recursion)?,
+        },
+        "f" => {
+            let selectors = fields(field(&mut fields(value)?, "f>")?)?;
+            Selector::ExploreFields {
+                fields: selectors
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, parse_selector(value, in_recursion)?)))
+                    .collect::<Result<_, _>>()?,
+            }
+        }
+        "i" => {
+            let mut value = fields(value)?;
+            Selector::ExploreIndex {
+                index: integer(field(&mut value, "i")?)?,
+                next: next(&mut value, in_recursion)?,
+            }
+        }
+        "r" => {
+            let mut value = fields(value)?;
+            let start = integer(field(&mut value, "^")?)?;
+            let end = integer(field(&mut value, "$")?)?;
+            if start > end {
+                return Err(SelectorError(format!(
+                    "range start {} is bigger than its end {}",
+                    start, end
+                )));
+            }
+            Selector::ExploreRange {
+                start,
+                end,
+                next: next(&mut value, in_recursion)?,
+            }
+        }
+        "R" => {
+            let mut value = fields(value)?;
+            let limit = match union_member(field(&mut value, "l")?)? {
+                (key, _) if key == "none" => RecursionLimit::None,
+                (key, depth) if key == "depth" => RecursionLimit::Depth(integer(depth)?),
+                (key, _) => {
+                    return Err(SelectorError(format!("unknown recursion limit `{}`", key)))
+                }
+            };
+            let stop_at = value.remove("!").map(parse_condition).transpose()?;
+            Selector::ExploreRecursive {
+                limit,
+                sequence: Box::new(parse_selector(field(&mut value, ":>")?, true)?),
+                stop_at,
+            }
+        }
+        "@" => {
+            if !in_recursion {
+                return Err(SelectorError(
+                    "recursive edge outside of a recursive selector".to_string(),
+                ));
+            }
+            Selector::ExploreRecursiveEdge
+        }
+        "|" => match value {
+            Ipld::List(selectors) => Selector::ExploreUnion(
+                selectors
+                    .into_iter()
+                    .map(|selector| parse_selector(selector, in_recursion))
+                    .collect::<Result<_, _>>()?,
+            ),
+            other => {
+                return Err(SelectorError(format!(
+                    "expected a List but found {:?}",
+                    other.kind()
+                )))
+            }
+        },
+        "&" => {
+            let mut value = fields(value)?;
+            Selector::ExploreConditional {
+                condition: parse_condition(field(&mut value, "&")?)?,
+                next: next(&mut value, in_recursion)?,
+            }
+        }
+        other => {
+            return Err(SelectorError(format!(
+                "unknown or unsupported selector `{}`",
+                other
+            )))
+        }
+    };
+    Ok(selector)
+}
+
+fn parse_condition(ipld: Ipld) -> Result<Condition, SelectorError> {
+    let conditions = |value| match value {
+        Ipld::List(conditions) => conditions
+            .into_iter()
+            .map(parse_condition)
+            .collect::<Result<_, _>>(),
+        other => Err(SelectorError(format!(
+            "expected a List but found {:?}",
+            other.kind()
+        ))),
+    };
+    let (key, value) = union_member(ipld)?;
+    match (key.as_str(), value) {
+        ("hasField", Ipld::String(field)) => Ok(Condition::HasField(field)),
+        ("=", value) => Ok(Condition::HasValue(value)),
+        ("%", Ipld::String(name)) => kind_from_name(&name)
+            .map(Condition::HasKind)
+            .ok_or_else(|| SelectorError(format!("unknown kind `{}`", name))),
+        ("/", _) => Ok(Condition::IsLink),
+        ("and", value) => Ok(Condition::And(conditions(value)?)),
+        ("or", value) => Ok(Condition::Or(conditions(value)?)),
+        (other, _) => Err(SelectorError(format!(
+            "unknown or unsupported condition `{}`",
+            other
+        ))),
+    }
+}
+
+/// Returns whether Lists and Maps are nested deeper than the given depth, without recursing.
+fn exceeds_depth(ipld: &Ipld, max_depth: usize) -> bool {
+    let mut stack = vec![(ipld, 0)];
+    while let Some((ipld, depth)) = stack.pop() {
+        match ipld {
+            Ipld::List(_) | Ipld::Map(_) if depth == max_depth => return true,
+            Ipld::List(list) => stack.extend(list.iter().map(|child| (child, depth + 1))),
+            Ipld::Map(map) => stack.extend(map.values().map(|child| (child, depth + 1))),
+            _ => {}
+        }
+    }
+    false
+}
+
+impl TryFrom<Ipld> for Selector {
+    type Error = SelectorError;
+
+    fn try_from(ipld: Ipld) -> Result<Self, Self::Error> {
+        // Limiting the depth upfront bounds the recursion when the selector is parsed, evaluated
+        // or dropped, as well as when parts of the input are dropped.
+        if exceeds_depth(&ipld, MAX_DEPTH) {
+            ipld.drop_iterative();
+            return Err(SelectorError(format!(
+                "maximum nesting depth of {} exceeded",
+                MAX_DEPTH
+            )));
+        }
+        parse_selector(ipld, false)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Selector {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Ipld::from(self.clone()).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Selector {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let ipld = Ipld::deserialize(deserializer)?;
+        Self::try_from(ipld).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::ipld;
+
+    fn select(selector: Ipld, ipld: &Ipld) -> Vec<(String, Ipld)> {
+        Selector::try_from(selector)
+            .unwrap()
+            .select(ipld)
+            .into_iter()
+            .map(|(path, node)| (path.to_string(), node.clone()))
+            .collect()
+    }
+
+    fn paths(selector: Ipld, ipld: &Ipld) -> Vec<String> {
+        select(selector, ipld)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    fn tree() -> Ipld {
+        ipld!({
+            "name": "root",
+            "children": [
+                {"name": "a", "children": [{"name": "a0", "children": []}]},
+                {"name": "b", "children": []},
+            ],
+        })
+    }
+
+    #[test]
+    fn test_matcher() {
+        let ipld = tree();
+        assert_eq!(select(ipld!({".": {}}), &ipld), [(String::new(), ipld)]);
+    }
+
+    #[test]
+    fn test_explore_all_fields() {
+        let selector = ipld!({"a": {">": {".": {}}}});
+        assert_eq!(
+            select(selector, &ipld!({"x": 1, "y": [2]})),
+            [("/x".to_string(), ipld!(1)), ("/y".to_string(), ipld!([2]))]
+        );
+
+        let selector = ipld!({"f": {"f>": {"name": {".": {}}, "missing": {".": {}}}}});
+        assert_eq!(
+            select(selector, &tree()),
+            [("/name".to_string(), ipld!("root"))]
+        );
+    }
+
+    #[test]
+    fn test_explore_index_range() {
+        let list = ipld!([0, 1, 2, 3, 4]);
+        assert_eq!(
+            select(ipld!({"i": {"i": 3, ">": {".": {}}}}), &list),
+            [("/3".to_string(), ipld!(3))]
+        );
+        assert_eq!(
+            paths(ipld!({"r": {"^": 1, "$": 3, ">": {".": {}}}}), &list),
+            ["/1", "/2"]
+        );
+        assert!(Selector::try_from(ipld!({"r": {"^": 3, "$": 1, ">": {".": {}}}})).is_err());
+    }
+
+    #[test]
+    fn test_explore_recursive() {
+        // Select the names of all nodes of the tree.
+        let selector = ipld!({"R": {
+            "l": {"none": {}},
+            ":>": {"f": {"f>": {
+                "name": {".": {}},
+                "children": {"a": {">": {"@": {}}}},
+            }}},
+        }});
+        assert_eq!(
+            paths(selector, &tree()),
+            [
+                "/children/0/children/0/name",
+                "/children/0/name",
+                "/children/1/name",
+                "/name"
+            ]
+        );
+
+        // The depth limits how often the sequence is applied.
+        let selector = ipld!({"R": {
+            "l": {"depth": 2},
+            ":>": {"f": {"f>": {
+                "name": {".": {}},
+                "children": {"a": {">": {"@": {}}}},
+            }}},
+        }});
+        assert_eq!(
+            paths(selector, &tree()),
+            ["/children/0/name", "/children/1/name", "/name"]
+        );
+
+        let selector = ipld!({"R": {"l": {"depth": 1}, ":>": {"a": {">": {"@": {}}}}}});
+        assert!(paths(selector, &tree()).is_empty());
+    }
+
+    #[test]
+    fn test_explore_recursive_all() {
+        // Matches every node.
+        let selector = ipld!({"R": {
+            "l": {"none": {}},
+            ":>": {"|": [{".": {}}, {"a": {">": {"@": {}}}}]},
+        }});
+        assert_eq!(
+            paths(selector, &ipld!({"a": [1, {"b": 2}]})),
+            ["", "/a", "/a/0", "/a/1", "/a/1/b"]
+        );
+
+        // An edge that doesn't explore doesn't loop forever.
+        let selector = ipld!({"R": {"l": {"none": {}}, ":>": {"|": [{".": {}}, {"@": {}}]}}});
+        assert_eq!(paths(selector, &ipld!([1])), [""]);
+    }
+
+    #[test]
+    fn test_explore_recursive_stop_at() {
+        let selector = ipld!({"R": {
+            "l": {"none": {}},
+            ":>": {"|": [{".": {}}, {"a": {">": {"@": {}}}}]},
+            "!": {"hasField": "stop"},
+        }});
+        assert_eq!(
+            paths(selector, &ipld!({"a": {"stop": 1}, "b": {"c": 2}})),
+            ["", "/b", "/b/c"]
+        );
+    }
+
+    #[test]
+    fn test_explore_conditional() {
+        let selector = ipld!({"a": {">": {"&": {
+            "&": {"or": [{"%": "string"}, {"=": 1}]},
+            ">": {".": {}},
+        }}}});
+        assert_eq!(paths(selector, &ipld!([1, 2, "three", null])), ["/0", "/2"]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Selector::try_from(ipld!({"@": {}})).is_err());
+        assert!(Selector::try_from(ipld!({"a": {}})).is_err());
+        assert!(Selector::try_from(ipld!({"x": {}})).is_err());
+        assert!(Selector::try_from(ipld!({".": {}, "a": {}})).is_err());
+        assert!(Selector::try_from(ipld!({"i": {"i": -1, ">": {".": {}}}})).is_err());
+        assert!(Selector::try_from(ipld!({"R": {"l": {"x": {}}, ":>": {".": {}}}})).is_err());
+
+        // Every level of `ExploreAll` is two levels of Maps.
+        let nested = |depth| (0..depth).fold(ipld!({".": {}}), |next, _| ipld!({"a": {">": next}}));
+        assert!(Selector::try_from(nested(MAX_DEPTH / 2 - 1)).is_ok());
+        assert!(Selector::try_from(nested(MAX_DEPTH / 2)).is_err());
+        // Far deeper selectors neither overflow the stack when parsed nor when dropped.
+        assert!(Selector::try_from(nested(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_ipld_roundtrip() {
+        let selectors = [
+            ipld!({".": {}}),
+            ipld!({"a": {">": {".": {}}}}),
+            ipld!({"f": {"f>": {"a": {".": {}}, "b": {"i": {"i": 1, ">": {".": {}}}}}}}),
+            ipld!({"r": {"^": 1, "$": 5, ">": {".": {}}}}),
+            ipld!({"R": {
+                "l": {"depth": 3},
+                ":>": {"|": [{".": {}}, {"a": {">": {"@": {}}}}]},
+                "!": {"and": [{"/": {}}, {"hasField": "x"}]},
+            }}),
+            ipld!({"&": {"&": {"=": [1, 2]}, ">": {".": {}}}}),
+        ];
+        for ipld in selectors {
+            let selector = Selector::try_from(ipld.clone()).unwrap();
+            assert_eq!(Ipld::from(selector), ipld);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        use crate::serde::from_ipld;
+
+        let selector: Selector = from_ipld(ipld!({"a": {">": {".": {}}}})).unwrap();
+        assert_eq!(
+            selector,
+            Selector::ExploreAll {
+                next: Box::new(Selector::Matcher)
+            }
+        );
+    }
+}
@@ -1,15 +1,25 @@
-use alloc::{borrow::ToOwned, collections::BTreeMap, format, string::String, vec::Vec};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    collections::BTreeMap,
+    format,
+    string::String,
+    vec::Vec,
+};
 use core::{convert::TryFrom, fmt};
-use serde::de::Error;
 
 use cid::serde::{BytesToCidVisitor, CID_SERDE_PRIVATE_IDENTIFIER};
 use cid::Cid;
 use serde::{
-    de::{self, IntoDeserializer},
+    de::{self, value::BorrowedStrDeserializer, DeserializeSeed, IntoDeserializer},
     forward_to_deserialize_any, Deserialize,
 };
 
-use crate::{ipld::Ipld, serde::SerdeError};
+#[cfg(target_has_atomic = "ptr")]
+use crate::ipld::SharedIpld;
+use crate::{
+    ipld::{Ipld, IpldRef},
+    serde::{IpldDeserializeOptions, Preview, SerdeError, SerdeErrorKind},
+};
 
 /// Deserialize instances of [`crate::ipld::Ipld`].
 ///
@@ -56,26 +66,383 @@ where
     T::deserialize(value)
 }
 
+/// Deserialize instances of [`crate::ipld::Ipld`] by reference.
+///
+/// Unlike [`from_ipld`] the value isn't consumed, hence it doesn't need to be cloned if it's still
+/// needed afterwards. Strings and bytes are passed on as borrowed, so that types like `&str` or
+/// `&[u8]` can point directly into the given value.
+///
+/// # Example
+///
+/// ```
+/// use serde_derive::Deserialize;
+/// use ipld_core::ipld;
+/// use ipld_core::serde::from_ipld_ref;
+///
+/// #[derive(Deserialize)]
+/// struct Person<'a> {
+///     name: &'a str,
+///     age: u8,
+/// }
+///
+/// let ipld = ipld!({"name": "Hello World!", "age": 52});
+///
+/// let person: Person = from_ipld_ref(&ipld).unwrap();
+/// assert_eq!(person.name, "Hello World!");
+/// assert_eq!(person.age, 52);
+/// ```
+pub fn from_ipld_ref<'de, T>(value: &'de Ipld) -> Result<T, SerdeError>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(value)
+}
+
+/// The nesting depth is bounded to [`IpldDeserializeOptions::DEFAULT_MAX_DEPTH`], so that the
+/// returned value can be dropped without overflowing the stack. Use [`IpldDeserializeOptions`]
+/// for other limits.
 impl<'de> de::Deserialize<'de> for Ipld {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: de::Deserializer<'de>,
     {
-        struct IpldVisitor;
+        IpldDeserializeOptions::new().deserialize(deserializer)
+    }
+}
+
+/// Limits that are checked while deserializing [`Ipld`].
+///
+/// They are implemented by the state of [`IpldDeserializeOptions`], which the [`de::Deserialize`]
+/// implementation of [`Ipld`] uses with their defaults.
+pub(crate) trait Limits {
+    /// Counts a node that isn't a List or Map.
+    fn node<E: de::Error>(&mut self) -> Result<(), E>;
+
+    /// Counts a List or Map node and enters it.
+    fn enter<E: de::Error>(&mut self) -> Result<(), E>;
+
+    /// Leaves a List or Map.
+    fn leave(&mut self);
+
+    /// Checks the length of a String or of a Map key.
+    fn check_string_len<E: de::Error>(&self, len: usize) -> Result<(), E>;
+
+    /// Checks the length of Bytes.
+    fn check_bytes_len<E: de::Error>(&self, len: usize) -> Result<(), E>;
+}
+
+/// Visits a single value, the limits are shared by all nested values.
+pub(crate) struct IpldVisitor<'a, L> {
+    pub(crate) limits: &'a mut L,
+}
+
+impl<L> IpldVisitor<'_, L> {
+    fn nested(&mut self) -> IpldVisitor<'_, L> {
+        IpldVisitor {
+            limits: self.limits,
+        }
+    }
+}
+
+impl<'de, L: Limits> de::DeserializeSeed<'de> for IpldVisitor<'_, L> {
+    type Value = Ipld;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, L: Limits> de::Visitor<'de> for IpldVisitor<'_, L> {
+    type Value = Ipld;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("any valid IPLD kind")
+    }
+
+    #[inline]
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        self.limits.check_string_len(value.len())?;
+        Ok(Ipld::String(String::from(value)))
+    }
+
+    #[inline]
+    fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        self.limits.check_string_len(value.len())?;
+        Ok(Ipld::String(value))
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        self.limits.check_bytes_len(v.len())?;
+        Ok(Ipld::Bytes(v.to_owned()))
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        self.limits.check_bytes_len(v.len())?;
+        Ok(Ipld::Bytes(v))
+    }
+
+    #[inline]
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        Ok(Ipld::Integer(v.into()))
+    }
+
+    #[inline]
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        Ok(Ipld::Integer(v.into()))
+    }
+
+    #[inline]
+    fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        Ok(Ipld::Integer(v))
+    }
+
+    #[inline]
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        Ok(Ipld::Float(v))
+    }
+
+    #[inline]
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        Ok(Ipld::Bool(v))
+    }
+
+    #[inline]
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        Ok(Ipld::Null)
+    }
+
+    #[inline]
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de::DeserializeSeed::deserialize(self, deserializer)
+    }
+
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.node()?;
+        Ok(Ipld::Null)
+    }
+
+    #[inline]
+    fn visit_seq<V>(mut self, mut visitor: V) -> Result<Self::Value, V::Error>
+    where
+        V: de::SeqAccess<'de>,
+    {
+        self.limits.enter()?;
+        let capacity = super::size_hint_cautious_ipld(visitor.size_hint().unwrap_or(0));
+        let mut vec = Vec::with_capacity(capacity);
+
+        loop {
+            match visitor.next_element_seed(self.nested()) {
+                Ok(Some(elem)) => vec.push(elem),
+                Ok(None) => break,
+                Err(error) => {
+                    // Partially deserialized values may be deeply nested.
+                    Ipld::List(vec).drop_iterative();
+                    return Err(error);
+                }
+            }
+        }
+
+        self.limits.leave();
+        Ok(Ipld::List(vec))
+    }
+
+    #[inline]
+    fn visit_map<V>(mut self, mut visitor: V) -> Result<Self::Value, V::Error>
+    where
+        V: de::MapAccess<'de>,
+    {
+        self.limits.enter()?;
+        let mut values = BTreeMap::new();
+
+        loop {
+            let entry = visitor
+                .next_key_seed(KeySeed {
+                    limits: &*self.limits,
+                })
+                .and_then(|key| match key {
+                    Some(key) => Ok(Some((key, visitor.next_value_seed(self.nested())?))),
+                    None => Ok(None),
+                });
+            let error = match entry {
+                Ok(Some((key, value))) => match values.insert(key, value) {
+                    Some(prev_value) => {
+                        prev_value.drop_iterative();
+                        de::Error::custom("Duplicate map key")
+                    }
+                    None => continue,
+                },
+                Ok(None) => break,
+                Err(error) => error,
+            };
+            // Partially deserialized values may be deeply nested.
+            Ipld::Map(values).drop_iterative();
+            return Err(error);
+        }
+
+        self.limits.leave();
+        Ok(Ipld::Map(values))
+    }
+
+    /// Newtype structs are only used to deserialize CIDs.
+    #[inline]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        self.limits.node()?;
+        deserializer
+            .deserialize_bytes(BytesToCidVisitor)
+            .map(Ipld::Link)
+    }
+}
+
+/// Deserializes a Map key, its length is checked against the limits.
+struct KeySeed<'a, L> {
+    limits: &'a L,
+}
+
+impl<'de, L: Limits> de::DeserializeSeed<'de> for KeySeed<'_, L> {
+    type Value = String;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_string(self)
+    }
+}
+
+impl<L: Limits> de::Visitor<'_> for KeySeed<'_, L> {
+    type Value = String;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("a string")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.check_string_len(value.len())?;
+        Ok(String::from(value))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.limits.check_string_len(value.len())?;
+        Ok(value)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<'de> de::Deserialize<'de> for SharedIpld {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        Ipld::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Strings, Bytes and Map keys are borrowed from the input where the deserializer supports it.
+impl<'de> de::Deserialize<'de> for IpldRef<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct IpldRefVisitor;
 
-        impl<'de> de::Visitor<'de> for IpldVisitor {
-            type Value = Ipld;
+        impl<'de> de::Visitor<'de> for IpldRefVisitor {
+            type Value = IpldRef<'de>;
 
             fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
                 fmt.write_str("any valid IPLD kind")
             }
 
+            #[inline]
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(IpldRef::String(Cow::Borrowed(value)))
+            }
+
             #[inline]
             fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
             where
                 E: de::Error,
             {
-                Ok(Ipld::String(String::from(value)))
+                Ok(IpldRef::String(Cow::Owned(String::from(value))))
+            }
+
+            #[inline]
+            fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(IpldRef::String(Cow::Owned(value)))
+            }
+
+            #[inline]
+            fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(IpldRef::Bytes(Cow::Borrowed(v)))
             }
 
             #[inline]
@@ -83,7 +450,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                self.visit_byte_buf(v.to_owned())
+                Ok(IpldRef::Bytes(Cow::Owned(v.to_owned())))
             }
 
             #[inline]
@@ -91,7 +458,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Bytes(v))
+                Ok(IpldRef::Bytes(Cow::Owned(v)))
             }
 
             #[inline]
@@ -99,7 +466,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Integer(v.into()))
+                Ok(IpldRef::Integer(v.into()))
             }
 
             #[inline]
@@ -107,7 +474,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Integer(v.into()))
+                Ok(IpldRef::Integer(v.into()))
             }
 
             #[inline]
@@ -115,7 +482,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Integer(v))
+                Ok(IpldRef::Integer(v))
             }
 
             #[inline]
@@ -123,7 +490,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Float(v))
+                Ok(IpldRef::Float(v))
             }
 
             #[inline]
@@ -131,7 +498,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Bool(v))
+                Ok(IpldRef::Bool(v))
             }
 
             #[inline]
@@ -139,7 +506,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Null)
+                Ok(IpldRef::Null)
             }
 
             #[inline]
@@ -155,7 +522,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
             where
                 E: de::Error,
             {
-                Ok(Ipld::Null)
+                Ok(IpldRef::Null)
             }
 
             #[inline]
@@ -170,7 +537,7 @@ impl<'de> de::Deserialize<'de> for Ipld {
                     vec.push(elem);
                 }
 
-                Ok(Ipld::List(vec))
+                Ok(IpldRef::List(vec))
             }
 
             #[inline]
@@ -180,14 +547,15 @@ impl<'de> de::Deserialize<'de> for Ipld {
             {
                 let mut values = BTreeMap::new();
 
-                while let Some((key, value)) = visitor.next_entry()? {
+                while let Some(key) = visitor.next_key_seed(BorrowedKey)? {
+                    let value = visitor.next_value()?;
                     let prev_value = values.insert(key, value);
                     if prev_value.is_some() {
                         return Err(de::Error::custom("Duplicate map key"));
                     }
                 }
 
-                Ok(Ipld::Map(values))
+                Ok(IpldRef::Map(values))
             }
 
             /// Newtype structs are only used to deserialize CIDs.
@@ -198,30 +566,74 @@ impl<'de> de::Deserialize<'de> for Ipld {
             {
                 deserializer
                     .deserialize_bytes(BytesToCidVisitor)
-                    .map(Ipld::Link)
+                    .map(IpldRef::Link)
+            }
+        }
+
+        /// Deserializes a Map key, it's borrowed if possible. The `Deserialize` implementation of
+        /// `Cow` always copies.
+        struct BorrowedKey;
+
+        impl<'de> de::DeserializeSeed<'de> for BorrowedKey {
+            type Value = Cow<'de, str>;
+
+            fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                deserializer.deserialize_str(self)
+            }
+        }
+
+        impl<'de> de::Visitor<'de> for BorrowedKey {
+            type Value = Cow<'de, str>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Cow::Borrowed(value))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Cow::Owned(String::from(value)))
+            }
+
+            fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(Cow::Owned(value))
             }
         }
 
-        deserializer.deserialize_any(IpldVisitor)
+        deserializer.deserialize_any(IpldRefVisitor)
     }
 }
 
 macro_rules! impl_deserialize_integer {
-    ($ty:ident, $deserialize:ident, $visit:ident) => {
+    ($ty:ident, $deserialize:ident, $visit:ident, $wrong_kind:ident) => {
         fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
-            match self {
-                Self::Integer(integer) => match $ty::try_from(integer) {
+            match &self {
+                Ipld::Integer(integer) => match $ty::try_from(*integer) {
                     Ok(int) => visitor.$visit(int),
-                    Err(_) => error(format!(
-                        "`Ipld::Integer` value was bigger than `{}`",
-                        stringify!($ty)
-                    )),
+                    Err(_) => error(
+                        SerdeErrorKind::OutOfRange,
+                        format!(
+                            "`Ipld::Integer` value `{}` is out of range for `{}`",
+                            integer,
+                            stringify!($ty)
+                        ),
+                    ),
                 },
-                _ => error(format!(
-                    "Only `Ipld::Integer` can be deserialized to `{}`, input was `{:#?}`",
-                    stringify!($ty),
-                    self
-                )),
+                _ => $wrong_kind("`Ipld::Integer`", concat!("`", stringify!($ty), "`"), self),
             }
         }
     };
@@ -238,7 +650,10 @@ impl<'de> de::Deserializer<'de> for CidDeserializer {
 
     #[inline]
     fn deserialize_any<V: de::Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
-        error("Only bytes can be deserialized into a CID")
+        error(
+            SerdeErrorKind::WrongKind,
+            "Only bytes can be deserialized into a CID",
+        )
     }
 
     fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
@@ -281,50 +696,42 @@ impl<'de> de::Deserializer<'de> for Ipld {
     fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::Null => visitor.visit_unit(),
-            _ => error(format!(
-                "Only `Ipld::Null` can be deserialized to unit, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Null`", "unit", self),
         }
     }
 
     fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::Bool(bool) => visitor.visit_bool(bool),
-            _ => error(format!(
-                "Only `Ipld::Bool` can be deserialized to bool, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Bool`", "bool", self),
         }
     }
 
-    impl_deserialize_integer!(i8, deserialize_i8, visit_i8);
-    impl_deserialize_integer!(i16, deserialize_i16, visit_i16);
-    impl_deserialize_integer!(i32, deserialize_i32, visit_i32);
-    impl_deserialize_integer!(i64, deserialize_i64, visit_i64);
+    impl_deserialize_integer!(i8, deserialize_i8, visit_i8, wrong_kind_owned);
+    impl_deserialize_integer!(i16, deserialize_i16, visit_i16, wrong_kind_owned);
+    impl_deserialize_integer!(i32, deserialize_i32, visit_i32, wrong_kind_owned);
+    impl_deserialize_integer!(i64, deserialize_i64, visit_i64, wrong_kind_owned);
 
-    impl_deserialize_integer!(u8, deserialize_u8, visit_u8);
-    impl_deserialize_integer!(u16, deserialize_u16, visit_u16);
-    impl_deserialize_integer!(u32, deserialize_u32, visit_u32);
-    impl_deserialize_integer!(u64, deserialize_u64, visit_u64);
+    impl_deserialize_integer!(u8, deserialize_u8, visit_u8, wrong_kind_owned);
+    impl_deserialize_integer!(u16, deserialize_u16, visit_u16, wrong_kind_owned);
+    impl_deserialize_integer!(u32, deserialize_u32, visit_u32, wrong_kind_owned);
+    impl_deserialize_integer!(u64, deserialize_u64, visit_u64, wrong_kind_owned);
 
     fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::Float(float) => {
                 if !float.is_finite() {
-                    error(format!("`Ipld::Float` must be a finite number, not infinity or NaN, input was `{}`", float))
+                    error(SerdeErrorKind::OutOfRange, format!("`Ipld::Float` must be a finite number, not infinity or NaN, input was `{}`", float))
                 } else if (float as f32) as f64 != float {
                     error(
+                        SerdeErrorKind::OutOfRange,
                         "`Ipld::Float` cannot be deserialized to `f32`, without loss of precision`",
                     )
                 } else {
                     visitor.visit_f32(float as f32)
                 }
             }
-            _ => error(format!(
-                "Only `Ipld::Float` can be deserialized to `f32`, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Float`", "`f32`", self),
         }
     }
 
@@ -334,13 +741,10 @@ impl<'de> de::Deserializer<'de> for Ipld {
                 if float.is_finite() {
                     visitor.visit_f64(float)
                 } else {
-                    error(format!("`Ipld::Float` must be a finite number, not infinity or NaN, input was `{}`", float))
+                    error(SerdeErrorKind::OutOfRange, format!("`Ipld::Float` must be a finite number, not infinity or NaN, input was `{}`", float))
                 }
             }
-            _ => error(format!(
-                "Only `Ipld::Float` can be deserialized to `f64`, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Float`", "`f64`", self),
         }
     }
 
@@ -350,43 +754,34 @@ impl<'de> de::Deserializer<'de> for Ipld {
                 if string.chars().count() == 1 {
                     visitor.visit_char(string.chars().next().unwrap())
                 } else {
-                    error("`Ipld::String` was longer than a single character")
+                    error(
+                        SerdeErrorKind::InvalidValue,
+                        "`Ipld::String` was longer than a single character",
+                    )
                 }
             }
-            _ => error(format!(
-                "Only `Ipld::String` can be deserialized to string, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::String`", "string", self),
         }
     }
 
     fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::String(string) => visitor.visit_str(&string),
-            _ => error(format!(
-                "Only `Ipld::String` can be deserialized to string, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::String`", "string", self),
         }
     }
 
     fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::String(string) => visitor.visit_string(string),
-            _ => error(format!(
-                "Only `Ipld::String` can be deserialized to string, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::String`", "string", self),
         }
     }
 
     fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::Bytes(bytes) => visitor.visit_bytes(&bytes),
-            _ => error(format!(
-                "Only `Ipld::Bytes` can be deserialized to bytes, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Bytes`", "bytes", self),
         }
     }
 
@@ -396,20 +791,14 @@ impl<'de> de::Deserializer<'de> for Ipld {
     ) -> Result<V::Value, Self::Error> {
         match self {
             Self::Bytes(bytes) => visitor.visit_byte_buf(bytes),
-            _ => error(format!(
-                "Only `Ipld::Bytes` can be deserialized to bytes, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Bytes`", "bytes", self),
         }
     }
 
     fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::List(list) => visit_seq(list, visitor),
-            _ => error(format!(
-                "Only `Ipld::List` can be deserialized to sequence, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::List`", "sequence", self),
         }
     }
 
@@ -420,10 +809,7 @@ impl<'de> de::Deserializer<'de> for Ipld {
     ) -> Result<V::Value, Self::Error> {
         match self {
             Self::List(list) => visit_seq(list, visitor),
-            _ => error(format!(
-                "Only `Ipld::List` can be deserialized to tuple, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::List`", "tuple", self),
         }
     }
 
@@ -439,10 +825,7 @@ impl<'de> de::Deserializer<'de> for Ipld {
     fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
         match self {
             Self::Map(map) => visit_map(map, visitor),
-            _ => error(format!(
-                "Only `Ipld::Map` can be deserialized to map, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Map`", "map", self),
         }
     }
 
@@ -452,10 +835,7 @@ impl<'de> de::Deserializer<'de> for Ipld {
     ) -> Result<V::Value, Self::Error> {
         match self {
             Self::String(string) => visitor.visit_str(&string),
-            _ => error(format!(
-                "Only `Ipld::String` can be deserialized to identifier, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::String`", "identifier", self),
         }
     }
 
@@ -467,10 +847,7 @@ impl<'de> de::Deserializer<'de> for Ipld {
     ) -> Result<V::Value, Self::Error> {
         match self {
             Self::Map(map) => visit_map(map, visitor),
-            _ => error(format!(
-                "Only `Ipld::Map` can be deserialized to struct, input was `{:#?}`",
-                self
-            )),
+            _ => wrong_kind_owned("`Ipld::Map`", "struct", self),
         }
     }
 
@@ -479,7 +856,7 @@ impl<'de> de::Deserializer<'de> for Ipld {
         _name: &str,
         _visitor: V,
     ) -> Result<V::Value, Self::Error> {
-        error("Unit struct cannot be deserialized")
+        error(SerdeErrorKind::Custom, "Unit struct cannot be deserialized")
     }
 
     fn deserialize_newtype_struct<V: de::Visitor<'de>>(
@@ -490,10 +867,7 @@ impl<'de> de::Deserializer<'de> for Ipld {
         if name == CID_SERDE_PRIVATE_IDENTIFIER {
             match self {
                 Ipld::Link(cid) => visitor.visit_newtype_struct(CidDeserializer(cid)),
-                _ => error(format!(
-                    "Only `Ipld::Link`s can be deserialized to CIDs, input was `{:#?}`",
-                    self
-                )),
+                _ => wrong_kind_owned("`Ipld::Link`s", "CIDs", self),
             }
         } else {
             visitor.visit_newtype_struct(self)
@@ -510,31 +884,34 @@ impl<'de> de::Deserializer<'de> for Ipld {
     ) -> Result<V::Value, Self::Error> {
         let (variant, value) = match self {
             Ipld::Map(map) => {
-                let mut iter = map.into_iter();
-                let (variant, value) = match iter.next() {
-                    Some(v) => v,
-                    None => {
-                        return error(
-                            "Only `Ipld::Map`s with a single key can be deserialized to `enum`, input had no keys"
-                        );
-                    }
-                };
                 // Enums are encoded in IPLD as maps with a single key-value pair
-                if iter.next().is_some() {
+                if map.len() != 1 {
+                    let found = if map.is_empty() {
+                        "no keys"
+                    } else {
+                        "more keys"
+                    };
+                    Ipld::Map(map).drop_iterative();
                     return error(
-                        "Only `Ipld::Map`s with a single key can be deserialized to `enum`, input had more keys"
+                        SerdeErrorKind::InvalidLength,
+                        format!("Only `Ipld::Map`s with a single key can be deserialized to `enum`, input had {}", found),
                     );
                 }
+                let (variant, value) = map.into_iter().next().expect("map has a single entry");
                 (variant, Some(value))
             }
             Ipld::String(variant) => (variant, None),
-            _ => return error(format!(
-                    "Only `Ipld::Map` and `Ipld::String` can be deserialized to `enum`, input was `{:#?}`",
-                    self
-            )),
+            _ => return wrong_kind_owned("`Ipld::Map` and `Ipld::String`", "`enum`", self),
         };
 
-        visitor.visit_enum(EnumDeserializer { variant, value })
+        // Errors of enums that are represented as Map are reported at the path of its only key.
+        let segment = value.as_ref().map(|_| variant.clone());
+        visitor
+            .visit_enum(EnumDeserializer { variant, value })
+            .map_err(|error| match segment {
+                Some(segment) => error.within(segment),
+                None => error,
+            })
     }
 
     // Heavily based on
@@ -543,7 +920,7 @@ impl<'de> de::Deserializer<'de> for Ipld {
         self,
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
-        drop(self);
+        self.drop_iterative();
         visitor.visit_unit()
     }
 
@@ -567,9 +944,10 @@ where
     let res = visitor.visit_map(&mut deserializer)?;
     match deserializer.remaining() {
         0 => Ok(res),
-        remaining => Err(SerdeError::custom(format!(
-            "The type failed to consume the entire map: {remaining} items remaining"
-        ))),
+        remaining => error(
+            SerdeErrorKind::InvalidLength,
+            format!("The type failed to consume the entire map: {remaining} items remaining"),
+        ),
     }
 }
 
@@ -581,9 +959,10 @@ where
     let res = visitor.visit_seq(&mut deserializer)?;
     match deserializer.remaining() {
         0 => Ok(res),
-        remaining => Err(SerdeError::custom(format!(
-            "The type failed to consume the entire sequence: {remaining} items remaining"
-        ))),
+        remaining => error(
+            SerdeErrorKind::InvalidLength,
+            format!("The type failed to consume the entire sequence: {remaining} items remaining"),
+        ),
     }
 }
 
@@ -591,6 +970,8 @@ where
 // https://github.com/serde-rs/json/blob/95f67a09399d546d9ecadeb747a845a77ff309b2/src/value/de.rs#L601
 struct MapDeserializer {
     iter: <BTreeMap<String, Ipld> as IntoIterator>::IntoIter,
+    /// The key of the current entry, it's used as path segment for errors.
+    key: String,
     value: Option<Ipld>,
 }
 
@@ -598,11 +979,24 @@ impl MapDeserializer {
     fn new(map: BTreeMap<String, Ipld>) -> Self {
         Self {
             iter: map.into_iter(),
+            key: String::new(),
             value: None,
         }
     }
     fn remaining(self) -> usize {
-        self.iter.count()
+        self.iter.len()
+    }
+}
+
+/// The entries that weren't deserialized are dropped without recursing.
+impl Drop for MapDeserializer {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            value.drop_iterative();
+        }
+        self.iter
+            .by_ref()
+            .for_each(|(_, value)| value.drop_iterative());
     }
 }
 
@@ -616,7 +1010,10 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
         match self.iter.next() {
             Some((key, value)) => {
                 self.value = Some(value);
-                seed.deserialize(Ipld::String(key)).map(Some)
+                self.key = key;
+                seed.deserialize(KeyDeserializer(&self.key))
+                    .map(Some)
+                    .map_err(|error| error.within(&self.key))
             }
             None => Ok(None),
         }
@@ -627,8 +1024,10 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
         T: de::DeserializeSeed<'de>,
     {
         match self.value.take() {
-            Some(value) => seed.deserialize(value),
-            None => error("value is missing"),
+            Some(value) => seed
+                .deserialize(value)
+                .map_err(|error| error.within(&self.key)),
+            None => error(SerdeErrorKind::Custom, "value is missing"),
         }
     }
 
@@ -640,20 +1039,115 @@ impl<'de> de::MapAccess<'de> for MapDeserializer {
     }
 }
 
-// Heavily based on
-// https://github.com/serde-rs/json/blob/95f67a09399d546d9ecadeb747a845a77ff309b2/src/value/de.rs#L554
-struct SeqDeserializer {
-    iter: <Vec<Ipld> as IntoIterator>::IntoIter,
+/// Forwards to the deserializer of an `Ipld::String` with a copy of the key.
+macro_rules! forward_to_ipld_string {
+    ($($deserialize:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            fn $deserialize<V: de::Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                Ipld::String(String::from(self.0)).$deserialize($($arg,)* visitor)
+            }
+        )*
+    };
 }
 
-impl SeqDeserializer {
-    fn new(vec: Vec<Ipld>) -> Self {
-        Self {
-            iter: vec.into_iter(),
-        }
-    }
+/// A Deserializer for Map keys that borrows the key, so that it's still available as path segment
+/// for errors.
+///
+/// It behaves like the Deserializer of an `Ipld::String`, but only copies the key if the visitor
+/// needs an owned `String`, or for uncommon key types.
+struct KeyDeserializer<'a>(&'a str);
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'_> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_string(String::from(self.0))
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            Ipld::String(String::from(self.0)).deserialize_newtype_struct(name, visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    forward_to_ipld_string! {
+        deserialize_bool() deserialize_i8() deserialize_i16() deserialize_i32() deserialize_i64()
+        deserialize_i128() deserialize_u8() deserialize_u16() deserialize_u32() deserialize_u64()
+        deserialize_u128() deserialize_f32() deserialize_f64() deserialize_char()
+        deserialize_bytes() deserialize_byte_buf() deserialize_unit() deserialize_seq()
+        deserialize_map()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+// Heavily based on
+// https://github.com/serde-rs/json/blob/95f67a09399d546d9ecadeb747a845a77ff309b2/src/value/de.rs#L554
+struct SeqDeserializer {
+    iter: <Vec<Ipld> as IntoIterator>::IntoIter,
+    /// The index of the next element, it's used as path segment for errors.
+    index: usize,
+}
+
+impl SeqDeserializer {
+    fn new(vec: Vec<Ipld>) -> Self {
+        Self {
+            iter: vec.into_iter(),
+            index: 0,
+        }
+    }
     fn remaining(self) -> usize {
-        self.iter.count()
+        self.iter.len()
+    }
+}
+
+/// The elements that weren't deserialized are dropped without recursing.
+impl Drop for SeqDeserializer {
+    fn drop(&mut self) {
+        self.iter.by_ref().for_each(Ipld::drop_iterative);
     }
 }
 
@@ -665,7 +1159,13 @@ impl<'de> de::SeqAccess<'de> for SeqDeserializer {
         T: de::DeserializeSeed<'de>,
     {
         match self.iter.next() {
-            Some(value) => seed.deserialize(value).map(Some),
+            Some(value) => {
+                let index = self.index;
+                self.index += 1;
+                seed.deserialize(value)
+                    .map(Some)
+                    .map_err(|error| error.within(index))
+            }
             None => Ok(None),
         }
     }
@@ -703,9 +1203,461 @@ impl<'de> de::EnumAccess<'de> for EnumDeserializer {
 // https://github.com/serde-rs/json/blob/95f67a09399d546d9ecadeb747a845a77ff309b2/src/value/de.rs#L482
 struct VariantDeserializer(Option<Ipld>);
 
+/// A value that wasn't deserialized is dropped without recursing.
+impl Drop for VariantDeserializer {
+    fn drop(&mut self) {
+        if let Some(value) = self.0.take() {
+            value.drop_iterative();
+        }
+    }
+}
+
 impl<'de> de::VariantAccess<'de> for VariantDeserializer {
     type Error = SerdeError;
 
+    fn unit_variant(mut self) -> Result<(), Self::Error> {
+        match self.0.take() {
+            Some(value) => de::Deserialize::deserialize(value),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(mut self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.0.take() {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(mut self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.0.take() {
+            Some(Ipld::List(list)) => {
+                if len == list.len() {
+                    visit_seq(list, visitor)
+                } else {
+                    let list_len = list.len();
+                    Ipld::List(list).drop_iterative();
+                    error(SerdeErrorKind::InvalidLength, format!("The tuple variant size must match the length of the `Ipld::List`, tuple variant size: {}, `Ipld::List` length: {}", len, list_len))
+                }
+            }
+            Some(value) => wrong_kind_owned("`Ipld::List`", "tuple variant", value),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V>(
+        mut self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.0.take() {
+            Some(Ipld::Map(v)) => visit_map(v, visitor),
+            Some(value) => wrong_kind_owned("`Ipld::Map`", "struct variant", value),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+/// Deserialize from a borrowed [`Ipld`] enum into a Rust type.
+///
+/// It behaves the same way as the implementation for an owned [`Ipld`], but strings and bytes are
+/// visited as borrowed from the input.
+impl<'de> de::Deserializer<'de> for &'de Ipld {
+    type Error = SerdeError;
+
+    #[inline]
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Ipld::Null => visitor.visit_none(),
+            Ipld::Bool(bool) => visitor.visit_bool(*bool),
+            Ipld::Integer(i128) => visitor.visit_i128(*i128),
+            Ipld::Float(f64) => visitor.visit_f64(*f64),
+            Ipld::String(string) => visitor.visit_borrowed_str(string),
+            Ipld::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            Ipld::List(list) => visit_seq_ref(list, visitor),
+            Ipld::Map(map) => visit_map_ref(map, visitor),
+            Ipld::Link(cid) => visitor.visit_newtype_struct(CidDeserializer(*cid)),
+        }
+    }
+
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Null => visitor.visit_unit(),
+            _ => wrong_kind("`Ipld::Null`", "unit", self),
+        }
+    }
+
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Bool(bool) => visitor.visit_bool(*bool),
+            _ => wrong_kind("`Ipld::Bool`", "bool", self),
+        }
+    }
+
+    impl_deserialize_integer!(i8, deserialize_i8, visit_i8, wrong_kind);
+    impl_deserialize_integer!(i16, deserialize_i16, visit_i16, wrong_kind);
+    impl_deserialize_integer!(i32, deserialize_i32, visit_i32, wrong_kind);
+    impl_deserialize_integer!(i64, deserialize_i64, visit_i64, wrong_kind);
+
+    impl_deserialize_integer!(u8, deserialize_u8, visit_u8, wrong_kind);
+    impl_deserialize_integer!(u16, deserialize_u16, visit_u16, wrong_kind);
+    impl_deserialize_integer!(u32, deserialize_u32, visit_u32, wrong_kind);
+    impl_deserialize_integer!(u64, deserialize_u64, visit_u64, wrong_kind);
+
+    fn deserialize_f32<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Float(_) => self.clone().deserialize_f32(visitor),
+            _ => wrong_kind("`Ipld::Float`", "`f32`", self),
+        }
+    }
+
+    fn deserialize_f64<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Float(_) => self.clone().deserialize_f64(visitor),
+            _ => wrong_kind("`Ipld::Float`", "`f64`", self),
+        }
+    }
+
+    fn deserialize_char<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::String(string) => {
+                let mut chars = string.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(char), None) => visitor.visit_char(char),
+                    _ => error(
+                        SerdeErrorKind::InvalidValue,
+                        "`Ipld::String` was longer than a single character",
+                    ),
+                }
+            }
+            _ => wrong_kind("`Ipld::String`", "string", self),
+        }
+    }
+
+    fn deserialize_str<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::String(string) => visitor.visit_borrowed_str(string),
+            _ => wrong_kind("`Ipld::String`", "string", self),
+        }
+    }
+
+    fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Bytes(bytes) => visitor.visit_borrowed_bytes(bytes),
+            _ => wrong_kind("`Ipld::Bytes`", "bytes", self),
+        }
+    }
+
+    fn deserialize_byte_buf<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::List(list) => visit_seq_ref(list, visitor),
+            _ => wrong_kind("`Ipld::List`", "sequence", self),
+        }
+    }
+
+    fn deserialize_tuple<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::List(list) => visit_seq_ref(list, visitor),
+            _ => wrong_kind("`Ipld::List`", "tuple", self),
+        }
+    }
+
+    fn deserialize_tuple_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Map(map) => visit_map_ref(map, visitor),
+            _ => wrong_kind("`Ipld::Map`", "map", self),
+        }
+    }
+
+    fn deserialize_identifier<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::String(string) => visitor.visit_borrowed_str(string),
+            _ => wrong_kind("`Ipld::String`", "identifier", self),
+        }
+    }
+
+    fn deserialize_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &str,
+        _fields: &[&str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Map(map) => visit_map_ref(map, visitor),
+            _ => wrong_kind("`Ipld::Map`", "struct", self),
+        }
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &str,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        error(SerdeErrorKind::Custom, "Unit struct cannot be deserialized")
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        name: &str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if name == CID_SERDE_PRIVATE_IDENTIFIER {
+            match self {
+                Ipld::Link(cid) => visitor.visit_newtype_struct(CidDeserializer(*cid)),
+                _ => wrong_kind("`Ipld::Link`s", "CIDs", self),
+            }
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &str,
+        _variants: &[&str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (variant, value) = match self {
+            Ipld::Map(map) => {
+                let mut iter = map.iter();
+                let (variant, value) = match iter.next() {
+                    Some(v) => v,
+                    None => {
+                        return error(
+                            SerdeErrorKind::InvalidLength,
+                            "Only `Ipld::Map`s with a single key can be deserialized to `enum`, input had no keys"
+                        );
+                    }
+                };
+                // Enums are encoded in IPLD as maps with a single key-value pair
+                if iter.next().is_some() {
+                    return error(
+                        SerdeErrorKind::InvalidLength,
+                        "Only `Ipld::Map`s with a single key can be deserialized to `enum`, input had more keys"
+                    );
+                }
+                (variant, Some(value))
+            }
+            Ipld::String(variant) => (variant, None),
+            _ => return wrong_kind("`Ipld::Map` and `Ipld::String`", "`enum`", self),
+        };
+
+        // Errors of enums that are represented as Map are reported at the path of its only key.
+        let segment = value.map(|_| variant);
+        visitor
+            .visit_enum(RefEnumDeserializer { variant, value })
+            .map_err(|error| match segment {
+                Some(segment) => error.within(segment),
+                None => error,
+            })
+    }
+
+    fn deserialize_ignored_any<V: de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self {
+            Ipld::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+fn visit_map_ref<'de, V>(
+    map: &'de BTreeMap<String, Ipld>,
+    visitor: V,
+) -> Result<V::Value, SerdeError>
+where
+    V: de::Visitor<'de>,
+{
+    let mut deserializer = RefMapDeserializer {
+        iter: map.iter(),
+        key: "",
+        value: None,
+    };
+    let res = visitor.visit_map(&mut deserializer)?;
+    match deserializer.iter.len() {
+        0 => Ok(res),
+        remaining => error(
+            SerdeErrorKind::InvalidLength,
+            format!("The type failed to consume the entire map: {remaining} items remaining"),
+        ),
+    }
+}
+
+fn visit_seq_ref<'de, V>(list: &'de [Ipld], visitor: V) -> Result<V::Value, SerdeError>
+where
+    V: de::Visitor<'de>,
+{
+    let mut deserializer = RefSeqDeserializer {
+        iter: list.iter(),
+        index: 0,
+    };
+    let res = visitor.visit_seq(&mut deserializer)?;
+    match deserializer.iter.len() {
+        0 => Ok(res),
+        remaining => error(
+            SerdeErrorKind::InvalidLength,
+            format!("The type failed to consume the entire sequence: {remaining} items remaining"),
+        ),
+    }
+}
+
+/// Borrowing counterpart of [`MapDeserializer`].
+struct RefMapDeserializer<'de> {
+    iter: alloc::collections::btree_map::Iter<'de, String, Ipld>,
+    key: &'de str,
+    value: Option<&'de Ipld>,
+}
+
+impl<'de> de::MapAccess<'de> for RefMapDeserializer<'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                self.key = key;
+                seed.deserialize(BorrowedStrDeserializer::<SerdeError>::new(key))
+                    .map(Some)
+                    .map_err(|error| error.within(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed
+                .deserialize(value)
+                .map_err(|error| error.within(self.key)),
+            None => error(SerdeErrorKind::Custom, "value is missing"),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Borrowing counterpart of [`SeqDeserializer`].
+struct RefSeqDeserializer<'de> {
+    iter: core::slice::Iter<'de, Ipld>,
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for RefSeqDeserializer<'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(value) => {
+                let index = self.index;
+                self.index += 1;
+                seed.deserialize(value)
+                    .map(Some)
+                    .map_err(|error| error.within(index))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Borrowing counterpart of [`EnumDeserializer`].
+struct RefEnumDeserializer<'de> {
+    variant: &'de str,
+    value: Option<&'de Ipld>,
+}
+
+impl<'de> de::EnumAccess<'de> for RefEnumDeserializer<'de> {
+    type Error = SerdeError;
+    type Variant = RefVariantDeserializer<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = BorrowedStrDeserializer::new(self.variant);
+        let visitor = RefVariantDeserializer(self.value);
+        seed.deserialize(variant).map(|v| (v, visitor))
+    }
+}
+
+/// Borrowing counterpart of [`VariantDeserializer`].
+struct RefVariantDeserializer<'de>(Option<&'de Ipld>);
+
+impl<'de> de::VariantAccess<'de> for RefVariantDeserializer<'de> {
+    type Error = SerdeError;
+
     fn unit_variant(self) -> Result<(), Self::Error> {
         match self.0 {
             Some(value) => de::Deserialize::deserialize(value),
@@ -733,15 +1685,12 @@ impl<'de> de::VariantAccess<'de> for VariantDeserializer {
         match self.0 {
             Some(Ipld::List(list)) => {
                 if len == list.len() {
-                    visit_seq(list, visitor)
+                    visit_seq_ref(list, visitor)
                 } else {
-                    error(format!("The tuple variant size must match the length of the `Ipld::List`, tuple variant size: {}, `Ipld::List` length: {}", len, list.len()))
+                    error(SerdeErrorKind::InvalidLength, format!("The tuple variant size must match the length of the `Ipld::List`, tuple variant size: {}, `Ipld::List` length: {}", len, list.len()))
                 }
             }
-            Some(_) => error(format!(
-                "Only `Ipld::List` can be deserialized to tuple variant, input was `{:#?}`",
-                self.0
-            )),
+            Some(value) => wrong_kind("`Ipld::List`", "tuple variant", value),
             None => Err(de::Error::invalid_type(
                 de::Unexpected::UnitVariant,
                 &"tuple variant",
@@ -758,11 +1707,8 @@ impl<'de> de::VariantAccess<'de> for VariantDeserializer {
         V: de::Visitor<'de>,
     {
         match self.0 {
-            Some(Ipld::Map(v)) => visit_map(v, visitor),
-            Some(_) => error(format!(
-                "Only `Ipld::Map` can be deserialized to struct variant, input was `{:#?}`",
-                self.0
-            )),
+            Some(Ipld::Map(map)) => visit_map_ref(map, visitor),
+            Some(value) => wrong_kind("`Ipld::Map`", "struct variant", value),
             None => Err(de::Error::invalid_type(
                 de::Unexpected::UnitVariant,
                 &"struct variant",
@@ -772,9 +1718,29 @@ impl<'de> de::VariantAccess<'de> for VariantDeserializer {
 }
 
 /// Returns a general error.
-fn error<S, T>(message: S) -> Result<T, SerdeError>
+fn error<S, T>(kind: SerdeErrorKind, message: S) -> Result<T, SerdeError>
 where
     S: AsRef<str> + fmt::Display,
 {
-    Err(de::Error::custom(message))
+    Err(SerdeError::new(kind, message))
+}
+
+/// Returns an error for an input value that has the wrong kind for the Rust type.
+/// Like [`wrong_kind`], but takes ownership of the input and drops it without recursing.
+fn wrong_kind_owned<T>(expected: &str, target: &str, input: Ipld) -> Result<T, SerdeError> {
+    let result = wrong_kind(expected, target, &input);
+    input.drop_iterative();
+    result
+}
+
+fn wrong_kind<T>(expected: &str, target: &str, input: &Ipld) -> Result<T, SerdeError> {
+    error(
+        SerdeErrorKind::WrongKind,
+        format!(
+            "Only {} can be deserialized to {}, input was `{}`",
+            expected,
+            target,
+            Preview(input)
+        ),
+    )
 }
@@ -16,6 +16,328 @@ impl<const S: usize> ExtractLinks<S> {
     pub fn into_vec(self) -> Vec<CidGeneric<S>> {
         self.links
     }
+
+    /// Extract links from untrusted data, deserialization stops as soon as a limit is exceeded.
+    ///
+    /// Unlike the [`de::Deserialize`] implementation, which collects the links of every nested
+    /// value separately, all links are appended to a single `Vec`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ipld_core::{codec::Codec, ipld, serde::{ExtractLinks, ExtractLinksError, LinkLimits}};
+    /// use serde_ipld_dagcbor::{codec::DagCborCodec, de::Deserializer};
+    ///
+    /// let bytes = DagCborCodec::encode_to_vec(&ipld!([[[1]]])).unwrap();
+    /// let mut deserializer = Deserializer::from_slice(&bytes);
+    /// let limits = LinkLimits::new().max_depth(2);
+    /// let result = ExtractLinks::<64>::deserialize_bounded(&mut deserializer, &limits);
+    /// assert!(matches!(result, Err(ExtractLinksError::MaxDepth(2))));
+    /// ```
+    pub fn deserialize_bounded<'de, D>(
+        deserializer: D,
+        limits: &LinkLimits,
+    ) -> Result<Self, ExtractLinksError<D::Error>>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let mut state = BoundedState {
+            limits: *limits,
+            depth: 0,
+            links: Vec::new(),
+            exceeded: None,
+        };
+        let result = deserializer.deserialize_any(BoundedVisitor { state: &mut state });
+        match (result, state.exceeded) {
+            (Ok(()), _) => Ok(Self { links: state.links }),
+            (Err(_), Some(Limit::Depth)) => Err(ExtractLinksError::MaxDepth(limits.max_depth)),
+            (Err(_), Some(Limit::Links)) => Err(ExtractLinksError::MaxLinks(limits.max_links)),
+            (Err(_), Some(Limit::CollectionLen)) => Err(ExtractLinksError::MaxCollectionLen(
+                limits.max_collection_len,
+            )),
+            (Err(error), None) => Err(ExtractLinksError::Deserialize(error)),
+        }
+    }
+}
+
+/// Limits for extracting links from untrusted data with [`ExtractLinks::deserialize_bounded`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkLimits {
+    max_depth: usize,
+    max_links: usize,
+    max_collection_len: usize,
+}
+
+impl LinkLimits {
+    /// The default maximum nesting depth.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// Creates limits that only bound the nesting depth, to [`Self::DEFAULT_MAX_DEPTH`].
+    pub fn new() -> Self {
+        Self {
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_links: usize::MAX,
+            max_collection_len: usize::MAX,
+        }
+    }
+
+    /// Sets the maximum number of Lists and Maps that are nested within each other.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Sets the maximum number of links, including duplicates.
+    pub fn max_links(mut self, max: usize) -> Self {
+        self.max_links = max;
+        self
+    }
+
+    /// Sets the maximum number of elements of a single List or entries of a single Map.
+    pub fn max_collection_len(mut self, max: usize) -> Self {
+        self.max_collection_len = max;
+        self
+    }
+}
+
+impl Default for LinkLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Error when extracting links with [`ExtractLinks::deserialize_bounded`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ExtractLinksError<E> {
+    /// Error when Lists and Maps are nested deeper than the maximum depth.
+    MaxDepth(usize),
+    /// Error when there are more links than the maximum.
+    MaxLinks(usize),
+    /// Error when a List or Map is longer than the maximum length.
+    MaxCollectionLen(usize),
+    /// Error returned by the deserializer.
+    Deserialize(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ExtractLinksError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaxDepth(max) => write!(f, "maximum depth of {} exceeded", max),
+            Self::MaxLinks(max) => write!(f, "maximum number of {} links exceeded", max),
+            Self::MaxCollectionLen(max) => {
+                write!(f, "maximum collection length of {} exceeded", max)
+            }
+            Self::Deserialize(error) => write!(f, "deserialization failed: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for ExtractLinksError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Deserialize(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The limit that was exceeded.
+#[derive(Clone, Copy, Debug)]
+enum Limit {
+    Depth,
+    Links,
+    CollectionLen,
+}
+
+/// The state that is shared by all nested values during a bounded extraction.
+struct BoundedState<const S: usize> {
+    limits: LinkLimits,
+    depth: usize,
+    links: Vec<CidGeneric<S>>,
+    exceeded: Option<Limit>,
+}
+
+/// Visits a single value during a bounded extraction.
+struct BoundedVisitor<'a, const S: usize> {
+    state: &'a mut BoundedState<S>,
+}
+
+impl<const S: usize> BoundedVisitor<'_, S> {
+    fn nested(&mut self) -> BoundedVisitor<'_, S> {
+        BoundedVisitor { state: self.state }
+    }
+
+    /// Records the exceeded limit, the returned error aborts the deserialization.
+    fn exceed<E: de::Error>(&mut self, limit: Limit) -> E {
+        self.state.exceeded = Some(limit);
+        E::custom("limit exceeded")
+    }
+
+    /// Enters a List or Map.
+    fn enter<E: de::Error>(&mut self) -> Result<(), E> {
+        if self.state.depth >= self.state.limits.max_depth {
+            return Err(self.exceed(Limit::Depth));
+        }
+        self.state.depth += 1;
+        Ok(())
+    }
+
+    /// Checks the length of a List or Map after an element was read.
+    fn check_len<E: de::Error>(&mut self, len: usize) -> Result<(), E> {
+        if len > self.state.limits.max_collection_len {
+            return Err(self.exceed(Limit::CollectionLen));
+        }
+        Ok(())
+    }
+}
+
+impl<'de, const S: usize> de::DeserializeSeed<'de> for BoundedVisitor<'_, S> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, const S: usize> de::Visitor<'de> for BoundedVisitor<'_, S> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("anything at all")
+    }
+
+    #[inline]
+    fn visit_bool<E>(self, _value: bool) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_i64<E>(self, _value: i64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_i128<E>(self, _value: i128) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_u64<E>(self, _value: u64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_u128<E>(self, _value: u128) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_f64<E>(self, _value: f64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_str<E>(self, _value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de::DeserializeSeed::deserialize(self, deserializer)
+    }
+
+    #[inline]
+    fn visit_newtype_struct<D>(mut self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        // No DAG-* format has the idea of a newtype struct. Therefore when visiting a newtype
+        // struct, we can be sure that it's from deserializing a CID.
+        if self.state.links.len() >= self.state.limits.max_links {
+            return Err(self.exceed(Limit::Links));
+        }
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        let cid =
+            CidGeneric::try_from(&bytes[..]).map_err(|_| de::Error::custom("Cannot decode CID"))?;
+        self.state.links.push(cid);
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        self.enter()?;
+        self.check_len(seq.size_hint().unwrap_or(0))?;
+        let mut len = 0;
+        while seq.next_element_seed(self.nested())?.is_some() {
+            len += 1;
+            self.check_len(len)?;
+        }
+        self.state.depth -= 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        self.enter()?;
+        self.check_len(map.size_hint().unwrap_or(0))?;
+        let mut len = 0;
+        // Keys are visited as well, they may be arbitrarily nested in non DAG-* formats.
+        while map.next_key_seed(self.nested())?.is_some() {
+            map.next_value_seed(self.nested())?;
+            len += 1;
+            self.check_len(len)?;
+        }
+        self.state.depth -= 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, _value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+
+    fn visit_enum<A>(mut self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        use serde::de::VariantAccess;
+        self.enter()?;
+        data.variant_seed(self.nested())?
+            .1
+            .newtype_variant_seed(self.nested())?;
+        self.state.depth -= 1;
+        Ok(())
+    }
 }
 
 impl<'de, const S: usize> de::Visitor<'de> for ExtractLinks<S> {
@@ -0,0 +1,227 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt;
+
+use cid::CidGeneric;
+use serde::{de, Deserialize};
+use serde_bytes::ByteBuf;
+
+use crate::{path::IpldPath, serde::LinkLimits};
+
+/// Extract links together with the paths they are at from an `ipld_serde_dag*` codec.
+///
+/// Like [`crate::serde::ExtractLinks`] the data isn't decoded into an [`crate::ipld::Ipld`]. The
+/// links are returned in the order they appear in the data, a link that appears several times is
+/// returned for each of its paths.
+///
+/// Lists and Maps may be nested at most [`LinkLimits::DEFAULT_MAX_DEPTH`] levels deep, so that
+/// untrusted data can't overflow the stack.
+#[derive(Debug)]
+pub struct ExtractLinkPaths<const S: usize> {
+    links: Vec<(IpldPath, CidGeneric<S>)>,
+}
+
+impl<const S: usize> ExtractLinkPaths<S> {
+    /// Get the extracted links (CIDs) together with their paths.
+    pub fn into_vec(self) -> Vec<(IpldPath, CidGeneric<S>)> {
+        self.links
+    }
+
+    /// Get the extracted links (CIDs) together with their paths.
+    pub fn links(&self) -> &[(IpldPath, CidGeneric<S>)] {
+        &self.links
+    }
+
+    /// Returns each distinct link together with the number of times it appears.
+    pub fn counts(&self) -> BTreeMap<CidGeneric<S>, usize> {
+        let mut counts = BTreeMap::new();
+        for (_, cid) in &self.links {
+            *counts.entry(*cid).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+impl<'de, const S: usize> de::Deserialize<'de> for ExtractLinkPaths<S> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let mut path = IpldPath::new();
+        let mut links = Vec::new();
+        de::DeserializeSeed::deserialize(
+            LinkPathsVisitor {
+                path: &mut path,
+                links: &mut links,
+            },
+            deserializer,
+        )?;
+        Ok(Self { links })
+    }
+}
+
+/// Visits a single value, the path and the links are shared by all nested values.
+struct LinkPathsVisitor<'a, const S: usize> {
+    path: &'a mut IpldPath,
+    links: &'a mut Vec<(IpldPath, CidGeneric<S>)>,
+}
+
+impl<const S: usize> LinkPathsVisitor<'_, S> {
+    fn nested(&mut self) -> LinkPathsVisitor<'_, S> {
+        LinkPathsVisitor {
+            path: self.path,
+            links: self.links,
+        }
+    }
+
+    /// Enters a List, Map or enum, each of them adds a segment to the path.
+    fn enter<E: de::Error>(&self) -> Result<(), E> {
+        if self.path.len() >= LinkLimits::DEFAULT_MAX_DEPTH {
+            return Err(E::custom(format_args!(
+                "maximum depth of {} exceeded",
+                LinkLimits::DEFAULT_MAX_DEPTH
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<'de, const S: usize> de::DeserializeSeed<'de> for LinkPathsVisitor<'_, S> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, const S: usize> de::Visitor<'de> for LinkPathsVisitor<'_, S> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("anything at all")
+    }
+
+    #[inline]
+    fn visit_bool<E>(self, _value: bool) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_i64<E>(self, _value: i64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_i128<E>(self, _value: i128) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_u64<E>(self, _value: u64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_u128<E>(self, _value: u128) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_f64<E>(self, _value: f64) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_str<E>(self, _value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        de::DeserializeSeed::deserialize(self, deserializer)
+    }
+
+    #[inline]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        // No DAG-* format has the idea of a newtype struct. Therefore when visiting a newtype
+        // struct, we can be sure that it's from deserializing a CID.
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        let cid =
+            CidGeneric::try_from(&bytes[..]).map_err(|_| de::Error::custom("Cannot decode CID"))?;
+        self.links.push((self.path.clone(), cid));
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        self.enter()?;
+        for index in 0.. {
+            self.path.push(index);
+            let element = seq.next_element_seed(self.nested())?;
+            self.path.pop();
+            if element.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_map<A>(mut self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        self.enter()?;
+        while let Some(key) = map.next_key::<String>()? {
+            self.path.push(key);
+            map.next_value_seed(self.nested())?;
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, _value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+
+    fn visit_enum<A>(mut self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        use serde::de::VariantAccess;
+        self.enter()?;
+        let (variant, access) = data.variant::<String>()?;
+        self.path.push(variant);
+        access.newtype_variant_seed(self.nested())?;
+        self.path.pop();
+        Ok(())
+    }
+}
@@ -4,41 +4,213 @@
 //! values. The `Ipld` enum is similar to the `Value` enum in `serde_json` or `serde_cbor`.
 mod de;
 mod extract_links;
+mod link_paths;
+mod options;
 mod ser;
 
-use alloc::string::{String, ToString};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
 use core::{fmt, mem};
 
-use crate::ipld::Ipld;
+use serde::de::{Expected, Unexpected};
 
-pub use de::from_ipld;
-pub use extract_links::ExtractLinks;
+use crate::{ipld::Ipld, path::IpldPath};
+
+pub use de::{from_ipld, from_ipld_ref};
+pub use extract_links::{ExtractLinks, ExtractLinksError, LinkLimits};
+pub use link_paths::ExtractLinkPaths;
+pub use options::IpldDeserializeOptions;
 pub use ser::{to_ipld, Serializer};
 
+/// The kind of a [`SerdeError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SerdeErrorKind {
+    /// The IPLD kind of the value doesn't match the one the Rust type expects.
+    WrongKind,
+    /// A number doesn't fit into the Rust type without overflow or loss of precision.
+    OutOfRange,
+    /// The value has the right kind, but isn't valid for the Rust type.
+    InvalidValue,
+    /// A List or Map has a different number of elements than expected.
+    InvalidLength,
+    /// The name of an enum variant isn't known.
+    UnknownVariant,
+    /// A Map contains a key that doesn't correspond to a struct field.
+    UnknownField,
+    /// A Map doesn't contain a key for a required struct field.
+    MissingField,
+    /// Any other error, e.g. from a custom (de)serialization implementation.
+    Custom,
+}
+
 /// Error during Serde operations.
+///
+/// Errors that occur during deserialization from [`Ipld`] contain the path to the value that
+/// failed to deserialize.
 #[derive(Clone, Debug)]
-pub struct SerdeError(String);
+pub struct SerdeError {
+    kind: SerdeErrorKind,
+    message: String,
+    /// The segments of the path, from the innermost to the outermost one, as they are added while
+    /// the error is propagated upwards.
+    segments: Vec<String>,
+}
+
+impl SerdeError {
+    pub(crate) fn new<T: fmt::Display>(kind: SerdeErrorKind, message: T) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> SerdeErrorKind {
+        self.kind
+    }
+
+    /// Returns the error message, without the path.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the path to the value the error occurred at.
+    ///
+    /// The path is empty if the error occurred at the root of the value, or if it isn't related
+    /// to a specific value, e.g. on serialization.
+    pub fn path(&self) -> IpldPath {
+        self.segments.iter().rev().cloned().collect()
+    }
+
+    /// Prepends a segment to the path of the error.
+    pub(crate) fn within<T: fmt::Display>(mut self, segment: T) -> Self {
+        self.segments.push(segment.to_string());
+        self
+    }
+}
 
 impl fmt::Display for SerdeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "serde error: {}", self.0)
+        if self.segments.is_empty() {
+            write!(f, "serde error: {}", self.message)
+        } else {
+            write!(f, "serde error at `{}`: {}", self.path(), self.message)
+        }
     }
 }
 
 impl serde::de::Error for SerdeError {
     fn custom<T: fmt::Display>(message: T) -> Self {
-        Self(message.to_string())
+        Self::new(SerdeErrorKind::Custom, message)
+    }
+
+    fn invalid_type(unexpected: Unexpected, expected: &dyn Expected) -> Self {
+        Message::invalid_type(unexpected, expected).with_kind(SerdeErrorKind::WrongKind)
+    }
+
+    fn invalid_value(unexpected: Unexpected, expected: &dyn Expected) -> Self {
+        Message::invalid_value(unexpected, expected).with_kind(SerdeErrorKind::InvalidValue)
+    }
+
+    fn invalid_length(len: usize, expected: &dyn Expected) -> Self {
+        Message::invalid_length(len, expected).with_kind(SerdeErrorKind::InvalidLength)
+    }
+
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        Message::unknown_variant(variant, expected).with_kind(SerdeErrorKind::UnknownVariant)
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Message::unknown_field(field, expected).with_kind(SerdeErrorKind::UnknownField)
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Message::missing_field(field).with_kind(SerdeErrorKind::MissingField)
     }
 }
 
 impl serde::ser::Error for SerdeError {
     fn custom<T: fmt::Display>(message: T) -> Self {
-        Self(message.to_string())
+        Self::new(SerdeErrorKind::Custom, message)
     }
 }
 
 impl serde::ser::StdError for SerdeError {}
 
+/// An error that only carries a message.
+///
+/// It is used to get the messages of Serde's default implementations of [`serde::de::Error`].
+#[derive(Debug)]
+struct Message(String);
+
+impl Message {
+    fn with_kind(self, kind: SerdeErrorKind) -> SerdeError {
+        SerdeError::new(kind, self.0)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl serde::de::Error for Message {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Self(message.to_string())
+    }
+}
+
+impl serde::de::StdError for Message {}
+
+/// Formats a value for error messages, it's truncated if its debug output is too long.
+pub(crate) struct Preview<'a>(pub(crate) &'a Ipld);
+
+impl fmt::Display for Preview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const MAX_PREVIEW_LEN: usize = 64;
+
+        /// Writes until the limit is reached, then it errors in order to stop the formatting.
+        struct Truncated {
+            preview: String,
+            truncated: bool,
+        }
+
+        impl fmt::Write for Truncated {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let remaining = MAX_PREVIEW_LEN - self.preview.len();
+                if s.len() <= remaining {
+                    self.preview.push_str(s);
+                    return Ok(());
+                }
+                let mut end = remaining;
+                while !s.is_char_boundary(end) {
+                    end -= 1;
+                }
+                self.preview.push_str(&s[..end]);
+                self.truncated = true;
+                Err(fmt::Error)
+            }
+        }
+
+        let mut truncated = Truncated {
+            preview: String::new(),
+            truncated: false,
+        };
+        // An error only signals that the output was truncated.
+        let _ = fmt::write(&mut truncated, format_args!("{:?}", self.0));
+        f.write_str(&truncated.preview)?;
+        if truncated.truncated {
+            f.write_str("...")?;
+        }
+        Ok(())
+    }
+}
+
 // Limit the the number of bytes that are used for preallocating `Vec`s. This follows what Serde is
 // doing internally with `serde::private::size_hint::cautious()`.
 fn size_hint_cautious_ipld(size_hint: usize) -> usize {
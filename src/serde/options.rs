@@ -0,0 +1,153 @@
+use alloc::format;
+
+use serde::de;
+
+use crate::{
+    ipld::Ipld,
+    serde::de::{IpldVisitor, Limits},
+};
+
+/// Options for deserializing [`Ipld`] from untrusted data.
+///
+/// The [`de::Deserialize`] implementation of [`Ipld`] uses the default options, which only bound
+/// the nesting depth. The options are a [`de::DeserializeSeed`], deserializing with them fails as
+/// soon as a limit is exceeded.
+///
+/// # Example
+///
+/// ```
+/// use ipld_core::{codec::Codec, ipld, serde::IpldDeserializeOptions};
+/// use serde::de::DeserializeSeed;
+/// use serde_ipld_dagcbor::{codec::DagCborCodec, de::Deserializer};
+///
+/// let bytes = DagCborCodec::encode_to_vec(&ipld!({"list": [1, 2, 3]})).unwrap();
+/// let options = IpldDeserializeOptions::new().max_depth(2).max_nodes(5);
+/// let ipld = options.deserialize(&mut Deserializer::from_slice(&bytes)).unwrap();
+/// assert_eq!(ipld, ipld!({"list": [1, 2, 3]}));
+///
+/// let options = IpldDeserializeOptions::new().max_nodes(4);
+/// assert!(options.deserialize(&mut Deserializer::from_slice(&bytes)).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpldDeserializeOptions {
+    max_depth: usize,
+    max_nodes: usize,
+    max_string_len: usize,
+    max_bytes_len: usize,
+}
+
+impl IpldDeserializeOptions {
+    /// The default maximum nesting depth.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// Creates options that only bound the nesting depth, to [`Self::DEFAULT_MAX_DEPTH`].
+    pub fn new() -> Self {
+        Self {
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            max_nodes: usize::MAX,
+            max_string_len: usize::MAX,
+            max_bytes_len: usize::MAX,
+        }
+    }
+
+    /// Sets the maximum number of Lists and Maps that are nested within each other.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Sets the maximum number of nodes, including the root and all Lists and Maps.
+    pub fn max_nodes(mut self, max: usize) -> Self {
+        self.max_nodes = max;
+        self
+    }
+
+    /// Sets the maximum length in bytes of a String or of a Map key.
+    pub fn max_string_len(mut self, max: usize) -> Self {
+        self.max_string_len = max;
+        self
+    }
+
+    /// Sets the maximum length of Bytes.
+    pub fn max_bytes_len(mut self, max: usize) -> Self {
+        self.max_bytes_len = max;
+        self
+    }
+}
+
+impl Default for IpldDeserializeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'de> de::DeserializeSeed<'de> for IpldDeserializeOptions {
+    type Value = Ipld;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let mut state = LimitedState {
+            options: self,
+            depth: 0,
+            nodes: 0,
+        };
+        deserializer.deserialize_any(IpldVisitor { limits: &mut state })
+    }
+}
+
+/// The state that is shared by all nested values.
+struct LimitedState {
+    options: IpldDeserializeOptions,
+    depth: usize,
+    nodes: usize,
+}
+
+impl Limits for LimitedState {
+    fn node<E: de::Error>(&mut self) -> Result<(), E> {
+        if self.nodes >= self.options.max_nodes {
+            return Err(E::custom(format!(
+                "maximum number of {} nodes exceeded",
+                self.options.max_nodes
+            )));
+        }
+        self.nodes += 1;
+        Ok(())
+    }
+
+    fn enter<E: de::Error>(&mut self) -> Result<(), E> {
+        self.node()?;
+        if self.depth >= self.options.max_depth {
+            return Err(E::custom(format!(
+                "maximum depth of {} exceeded",
+                self.options.max_depth
+            )));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    fn check_string_len<E: de::Error>(&self, len: usize) -> Result<(), E> {
+        check_len(len, self.options.max_string_len, "string")
+    }
+
+    fn check_bytes_len<E: de::Error>(&self, len: usize) -> Result<(), E> {
+        check_len(len, self.options.max_bytes_len, "bytes")
+    }
+}
+
+/// Checks the length of a String or Bytes.
+fn check_len<E: de::Error>(len: usize, max: usize, kind: &str) -> Result<(), E> {
+    if len > max {
+        return Err(E::custom(format!(
+            "maximum {} length of {} exceeded: {}",
+            kind, max, len
+        )));
+    }
+    Ok(())
+}
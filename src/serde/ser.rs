@@ -13,6 +13,8 @@ use cid::serde::CID_SERDE_PRIVATE_IDENTIFIER;
 use cid::Cid;
 use serde::ser;
 
+#[cfg(target_has_atomic = "ptr")]
+use crate::ipld::SharedIpld;
 use crate::{ipld::Ipld, serde::SerdeError};
 
 /// Serialize into instances of [`crate::ipld::Ipld`].
@@ -98,6 +100,16 @@ impl ser::Serialize for Ipld {
     }
 }
 
+#[cfg(target_has_atomic = "ptr")]
+impl ser::Serialize for SharedIpld {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        (**self).serialize(serializer)
+    }
+}
+
 /// The IPLD serializer.
 pub struct Serializer;
 
@@ -0,0 +1,407 @@
+//! Traversal of IPLD data across block boundaries.
+//!
+//! A [`Traversal`] starts at a root block, it loads blocks through a [`BlockLoader`] and decodes
+//! them with the codec that matches the codec code of their CID. Links are followed
+//! transparently, the path of a node is the path from the root block, as if all links were
+//! replaced by the data they point to.
+//!
+//! Budgets limit the number of blocks that are loaded and the number of links that are followed
+//! in a row, so that untrusted data cannot keep a traversal going forever. They are bounded by
+//! default and can be raised if needed.
+//!
+//! With the `hash` feature enabled, the data of every loaded block is verified against the hash of
+//! its CID. Without it, the block loader needs to be trusted to return the correct data.
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    hash::BuildHasher,
+    io,
+};
+
+use cid::Cid;
+
+use crate::{
+    blockstore::{Blockstore, FsBlockstore, MemoryBlockstore},
+    codec::{inline_data, Codec, CodecRegistry, DynCodecError},
+    ipld::{Ipld, IpldIndex},
+    path::IpldPath,
+    selector::{PendingState, RecursionLimit, Selector},
+};
+
+/// Loads the encoded data of a block.
+///
+/// Implementations don't need to verify that the data matches the CID. A [`Traversal`] verifies
+/// it if the `hash` feature is enabled, otherwise it relies on the loader to return the correct
+/// data.
+pub trait BlockLoader {
+    /// The error that is returned if a block cannot be loaded.
+    type Error;
+
+    /// Returns the encoded data of the block with the given CID.
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error>;
+}
+
+impl<L: BlockLoader + ?Sized> BlockLoader for &mut L {
+    type Error = L::Error;
+
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+        (**self).load(cid)
+    }
+}
+
+/// Error when a block is not available.
+#[derive(Clone, Debug)]
+pub struct BlockNotFound(pub Cid);
+
+impl fmt::Display for BlockNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block not found: {}", self.0)
+    }
+}
+
+impl Error for BlockNotFound {}
+
+impl BlockLoader for BTreeMap<Cid, Vec<u8>> {
+    type Error = BlockNotFound;
+
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+        self.get(cid).cloned().ok_or(BlockNotFound(*cid))
+    }
+}
+
+impl<S: BuildHasher> BlockLoader for HashMap<Cid, Vec<u8>, S> {
+    type Error = BlockNotFound;
+
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+        self.get(cid).cloned().ok_or(BlockNotFound(*cid))
+    }
+}
+
+impl BlockLoader for MemoryBlockstore {
+    type Error = BlockNotFound;
+
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+        match self.get(cid) {
+            Ok(data) => data.ok_or(BlockNotFound(*cid)),
+            Err(infallible) => match infallible {},
+        }
+    }
+}
+
+impl BlockLoader for FsBlockstore {
+    type Error = io::Error;
+
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+        self.get(cid)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, BlockNotFound(*cid)))
+    }
+}
+
+#[cfg(feature = "car")]
+impl<R: io::BufRead + io::Seek> BlockLoader for crate::car::CarV2Reader<R> {
+    type Error = crate::car::CarError;
+
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+        self.get(cid)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, BlockNotFound(*cid)).into())
+    }
+}
+
+/// Error during a traversal.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TraversalError<E> {
+    /// Error when loading a block failed.
+    Load {
+        /// The CID of the block.
+        cid: Cid,
+        /// The error returned by the block loader.
+        source: E,
+    },
+    /// Error when decoding a block failed.
+    Decode {
+        /// The CID of the block.
+        cid: Cid,
+        /// The error returned by the codec.
+        source: DynCodecError,
+    },
+    /// Error when there is no codec for the codec code of a CID.
+    UnknownCodec {
+        /// The CID of the block.
+        cid: Cid,
+    },
+    /// Error when more blocks than the given maximum would be loaded.
+    MaxBlocks(usize),
+    /// Error when more links than the given maximum would be followed in a row.
+    MaxDepth(usize),
+    /// Error when the data of a block doesn't match its CID.
+    #[cfg(feature = "hash")]
+    Hash {
+        /// The CID of the block.
+        cid: Cid,
+        /// The error returned when verifying the hash.
+        source: Box<crate::hash::HashError>,
+    },
+}
+
+impl<E: fmt::Display> fmt::Display for TraversalError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load { cid, source } => write!(f, "cannot load block {}: {}", cid, source),
+            Self::Decode { cid, source } => write!(f, "cannot decode block {}: {}", cid, source),
+            Self::UnknownCodec { cid } => {
+                write!(f, "no codec for code {:#x} of block {}", cid.codec(), cid)
+            }
+            Self::MaxBlocks(max) => write!(f, "maximum number of blocks ({}) exceeded", max),
+            Self::MaxDepth(max) => write!(f, "maximum link depth ({}) exceeded", max),
+            #[cfg(feature = "hash")]
+            Self::Hash { cid, source } => write!(f, "cannot verify block {}: {}", cid, source),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for TraversalError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Load { source, .. } => Some(source),
+            Self::Decode { source, .. } => Some(&**source),
+            #[cfg(feature = "hash")]
+            Self::Hash { source, .. } => Some(&**source),
+            _ => None,
+        }
+    }
+}
+
+/// Traverses IPLD data across block boundaries.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use ipld_core::{cid::Cid, codec::Codec, ipld, traversal::Traversal};
+/// use serde_ipld_dagcbor::codec::DagCborCodec;
+///
+/// let child_cid =
+///     Cid::try_from("bafyreic4y7qn4fo5y7u6vv32y3z573ynonebawio6q5yhi7dxbtzzwtjoa").unwrap();
+/// let root_cid =
+///     Cid::try_from("bafyreighjrhufxbwiws5bcsv4buep57w2nhsk4mqq2mljyoolu4aa2ycka").unwrap();
+/// let blocks = HashMap::from([
+///     (child_cid, DagCborCodec::encode_to_vec(&ipld!({"name": "child"})).unwrap()),
+///     (root_cid, DagCborCodec::encode_to_vec(&ipld!({"child": child_cid})).unwrap()),
+/// ]);
+///
+/// let mut traversal = Traversal::new(blocks)
+///     .with_codec::<DagCborCodec>()
+///     .max_blocks(1000)
+///     .max_depth(32);
+/// let mut paths = Vec::new();
+/// traversal.walk(&root_cid, |path, _| paths.push(path.to_string())).unwrap();
+/// assert_eq!(paths, ["", "/child", "/child/name"]);
+/// ```
+pub struct Traversal<L> {
+    loader: L,
+    registry: CodecRegistry,
+    max_blocks: usize,
+    max_depth: usize,
+    #[cfg(feature = "hash")]
+    verify_hashes: bool,
+}
+
+impl<L: BlockLoader> Traversal<L> {
+    /// The default maximum number of blocks that are loaded during a single traversal.
+    pub const DEFAULT_MAX_BLOCKS: usize = 10_000;
+    /// The default maximum number of links that are followed in a row.
+    pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+    /// Creates a traversal that loads blocks from the given loader.
+    ///
+    /// No codecs are registered, the budgets are [`Self::DEFAULT_MAX_BLOCKS`] and
+    /// [`Self::DEFAULT_MAX_DEPTH`].
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            registry: CodecRegistry::new(),
+            max_blocks: Self::DEFAULT_MAX_BLOCKS,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            #[cfg(feature = "hash")]
+            verify_hashes: true,
+        }
+    }
+
+    /// Registers a codec, it's used for all blocks whose CID has the codec code of `C`.
+    pub fn with_codec<C>(mut self) -> Self
+    where
+        C: Codec<Ipld> + 'static,
+        C::Error: Error + Send + Sync + 'static,
+        C::LinksError: Error + Send + Sync + 'static,
+    {
+        self.registry = self.registry.with_codec::<C>();
+        self
+    }
+
+    /// Uses the codecs of the given registry, it replaces all previously registered codecs.
+    pub fn with_registry(mut self, registry: CodecRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Sets the maximum number of blocks that are loaded during a single traversal, including
+    /// the root block. Blocks with an identity CID contain their data inline, they don't count.
+    pub fn max_blocks(mut self, max: usize) -> Self {
+        self.max_blocks = max;
+        self
+    }
+
+    /// Sets the maximum number of links that are followed in a row, starting from the root block.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    /// Sets whether the data of loaded blocks is verified against the hash of their CID, it's
+    /// enabled by default.
+    ///
+    /// Verification fails for CIDs with a hash function that isn't supported, disabling it allows
+    /// traversing those, if the block loader is trusted.
+    #[cfg(feature = "hash")]
+    pub fn verify_hashes(mut self, verify: bool) -> Self {
+        self.verify_hashes = verify;
+        self
+    }
+
+    /// Returns the block loader.
+    pub fn into_loader(self) -> L {
+        self.loader
+    }
+
+    /// Visits every node reachable from the root block.
+    ///
+    /// The nodes are visited in depth-first order, List elements in order and Map entries sorted
+    /// by key. Links are not visited themselves, the node they point to is visited instead.
+    pub fn walk<F>(&mut self, root: &Cid, visitor: F) -> Result<(), TraversalError<L::Error>>
+    where
+        F: FnMut(&IpldPath, &Ipld),
+    {
+        let all = Selector::ExploreRecursive {
+            limit: RecursionLimit::None,
+            sequence: Box::new(Selector::ExploreUnion(vec![
+                Selector::Matcher,
+                Selector::ExploreAll {
+                    next: Box::new(Selector::ExploreRecursiveEdge),
+                },
+            ])),
+            stop_at: None,
+        };
+        self.select(root, &all, visitor)
+    }
+
+    /// Visits every node reachable from the root block that is matched by the selector.
+    ///
+    /// Only blocks that the selector explores are loaded. The nodes are visited in the same order
+    /// as [`Selector::select`] returns them.
+    pub fn select<F>(
+        &mut self,
+        root: &Cid,
+        selector: &Selector,
+        mut visitor: F,
+    ) -> Result<(), TraversalError<L::Error>>
+    where
+        F: FnMut(&IpldPath, &Ipld),
+    {
+        let mut loaded = 0;
+        let root = self.load(root, &mut loaded, 0)?;
+        // The nodes are owned, so that the children of a node can be moved out of it once it was
+        // visited. The depth is the number of links followed to get to the node.
+        let mut stack = vec![(IpldPath::new(), root, PendingState::new(selector), 0)];
+        while let Some((path, node, pending, depth)) = stack.pop() {
+            let (node, depth) = match node {
+                Ipld::Link(cid) => match self.load(&cid, &mut loaded, depth + 1) {
+                    Ok(node) => (node, depth + 1),
+                    Err(error) => {
+                        // Loaded blocks are untrusted, don't recurse when dropping them.
+                        for (_, node, _, _) in stack {
+                            node.drop_iterative();
+                        }
+                        return Err(error);
+                    }
+                },
+                node => (node, depth),
+            };
+            let state = pending.resolve(&node);
+            if state.is_match() {
+                visitor(&path, &node);
+            }
+            if !state.is_exploring() {
+                node.drop_iterative();
+                continue;
+            }
+            let children: Vec<_> = match node {
+                Ipld::List(list) => list
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, child)| (IpldIndex::List(index), child))
+                    .collect(),
+                Ipld::Map(map) => map
+                    .into_iter()
+                    .map(|(key, child)| (IpldIndex::Map(key), child))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for (segment, child) in children.into_iter().rev() {
+                let pending = state.explore(&segment);
+                if pending.is_empty() {
+                    child.drop_iterative();
+                } else {
+                    stack.push((path.join(segment), child, pending, depth));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads and decodes a block while keeping track of the budgets.
+    fn load(
+        &mut self,
+        cid: &Cid,
+        loaded: &mut usize,
+        depth: usize,
+    ) -> Result<Ipld, TraversalError<L::Error>> {
+        if depth > self.max_depth {
+            return Err(TraversalError::MaxDepth(self.max_depth));
+        }
+        let codec = self
+            .registry
+            .get(cid.codec())
+            .ok_or(TraversalError::UnknownCodec { cid: *cid })?;
+        // Blocks with an identity CID are inline, they aren't loaded and don't count as loaded.
+        let loaded_bytes;
+        let bytes = match inline_data(cid) {
+            Some(bytes) => bytes,
+            None => {
+                if *loaded >= self.max_blocks {
+                    return Err(TraversalError::MaxBlocks(self.max_blocks));
+                }
+                loaded_bytes = self
+                    .loader
+                    .load(cid)
+                    .map_err(|source| TraversalError::Load { cid: *cid, source })?;
+                *loaded += 1;
+                #[cfg(feature = "hash")]
+                if self.verify_hashes {
+                    crate::hash::verify(cid, &loaded_bytes).map_err(|source| {
+                        TraversalError::Hash {
+                            cid: *cid,
+                            source: Box::new(source),
+                        }
+                    })?;
+                }
+                &loaded_bytes[..]
+            }
+        };
+        codec
+            .decode(bytes)
+            .map_err(|source| TraversalError::Decode { cid: *cid, source })
+    }
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "block")]
+
+use ipld_core::{
+    block::{Block, BlockError},
+    cid::{multihash::Multihash, Cid},
+    hash::{HashError, Identity, Sha2_256},
+    ipld,
+    ipld::Ipld,
+};
+use serde_ipld_dagcbor::codec::DagCborCodec;
+
+/// The multicodec code of DAG-JSON.
+const DAG_JSON: u64 = 0x0129;
+
+#[test]
+fn test_encode_decode() {
+    let cid = Cid::try_from("bafyreibvjvcv745gig4mvqs4hctx4zfkono4rjejm2ta6gtyzkqxfjeily").unwrap();
+    let ipld = ipld!({"name": "block", "link": cid, "list": [1, 2, 3]});
+
+    let block = Block::encode::<DagCborCodec, Sha2_256, _>(&ipld).unwrap();
+    assert_eq!(block.cid().codec(), 0x71);
+    assert_eq!(block.cid().hash().code(), 0x12);
+    assert_eq!(block.decode::<DagCborCodec, Ipld>().unwrap(), ipld);
+    assert_eq!(
+        block.links::<DagCborCodec>().unwrap().collect::<Vec<_>>(),
+        [cid]
+    );
+}
+
+#[test]
+fn test_new_verifies_hash() {
+    let block = Block::encode::<DagCborCodec, Sha2_256, _>(&ipld!([1, 2])).unwrap();
+    let (cid, data) = block.clone().into_inner();
+    assert_eq!(Block::new(cid, data.clone()).unwrap(), block);
+
+    let mut corrupted = data.clone();
+    corrupted[1] = 3;
+    assert!(matches!(
+        Block::new(cid, corrupted),
+        Err(BlockError::Hash(HashError::Mismatch(invalid))) if invalid == cid
+    ));
+
+    let unsupported = Cid::new_v1(0x71, Multihash::wrap(0x13, &[0; 64]).unwrap());
+    assert!(matches!(
+        Block::new(unsupported, data),
+        Err(BlockError::Hash(HashError::UnsupportedCode(0x13)))
+    ));
+}
+
+#[test]
+fn test_identity() {
+    let block = Block::encode::<DagCborCodec, Identity, _>(&ipld!("inline")).unwrap();
+    assert_eq!(block.cid().hash().digest(), block.data());
+    assert_eq!(
+        block.decode::<DagCborCodec, Ipld>().unwrap(),
+        ipld!("inline")
+    );
+
+    let large = Ipld::Bytes(vec![0; 64]);
+    assert!(matches!(
+        Block::encode::<DagCborCodec, Identity, _>(&large),
+        Err(BlockError::Hash(HashError::IdentityTooLarge(66)))
+    ));
+}
+
+#[test]
+fn test_decode_checks_codec() {
+    let block = Block::encode::<DagCborCodec, Sha2_256, _>(&ipld!(true)).unwrap();
+    let (cid, data) = block.into_inner();
+    let json_cid = Cid::new_v1(DAG_JSON, *cid.hash());
+    let block = Block::new(json_cid, data).unwrap();
+    assert!(matches!(
+        block.decode::<DagCborCodec, Ipld>(),
+        Err(BlockError::CodecMismatch {
+            expected: 0x71,
+            found: DAG_JSON
+        })
+    ));
+    assert!(matches!(
+        block.links::<DagCborCodec>(),
+        Err(BlockError::CodecMismatch {
+            expected: 0x71,
+            found: DAG_JSON
+        })
+    ));
+
+    let invalid = Block::new_unchecked(cid, vec![0xff]);
+    assert!(matches!(
+        invalid.decode::<DagCborCodec, Ipld>(),
+        Err(BlockError::Codec(_))
+    ));
+    assert!(matches!(
+        invalid.links::<DagCborCodec>(),
+        Err(BlockError::Codec(_))
+    ));
+}
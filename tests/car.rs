@@ -0,0 +1,364 @@
+#![cfg(feature = "car")]
+
+use std::io::Cursor;
+
+use ipld_core::{
+    car::{CarError, CarHeader, CarReader, CarV2Reader, CarV2Writer, CarWriter, IndexCodec},
+    cid::{multihash::Multihash, Cid},
+    ipld,
+};
+
+fn cid(index: u8) -> Cid {
+    Cid::new_v1(0x55, Multihash::wrap(0x12, &[index; 32]).unwrap())
+}
+
+fn varint(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+    bytes
+}
+
+/// Returns an encoded CARv1 header with the given version.
+///
+/// It's encoded as a map, so that the keys are sorted like DAG-CBOR requires.
+fn header(version: u64) -> Vec<u8> {
+    let header =
+        serde_ipld_dagcbor::to_vec(&ipld!({"version": version, "roots": [cid(0)]})).unwrap();
+    let mut bytes = varint(header.len() as u64);
+    bytes.extend(header);
+    bytes
+}
+
+fn read_all(car: &[u8]) -> Result<Vec<(Cid, Vec<u8>)>, CarError> {
+    CarReader::new(car)?.collect()
+}
+
+#[test]
+fn test_roundtrip() {
+    let blocks = vec![
+        (cid(1), b"one".to_vec()),
+        (cid(2), vec![0xff; 1000]),
+        (cid(3), Vec::new()),
+    ];
+    let mut writer = CarWriter::new(Vec::new(), vec![cid(1), cid(2)]).unwrap();
+    for (cid, data) in &blocks {
+        writer.write(cid, data).unwrap();
+    }
+    let car = writer.finish().unwrap();
+
+    let mut reader = CarReader::new(&car[..]).unwrap();
+    assert_eq!(reader.header(), &CarHeader::new(vec![cid(1), cid(2)]));
+    assert_eq!(
+        reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap(),
+        blocks
+    );
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_header_is_dag_cbor() {
+    let car = CarWriter::new(Vec::new(), vec![cid(0)])
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!(car, header(1));
+    assert_eq!(read_all(&car).unwrap(), []);
+}
+
+#[test]
+fn test_invalid_header() {
+    assert!(matches!(
+        CarReader::new(&[][..]),
+        Err(CarError::UnexpectedEof { offset: 0 })
+    ));
+    assert!(matches!(
+        CarReader::new(&[0x00][..]),
+        Err(CarError::EmptySection { offset: 0 })
+    ));
+    assert!(matches!(
+        CarReader::new(&[0x80, 0x00][..]),
+        Err(CarError::InvalidVarint { offset: 0 })
+    ));
+    assert!(matches!(
+        CarReader::new(&varint(u64::MAX)[..]),
+        Err(CarError::InvalidVarint { offset: 0 })
+    ));
+    assert!(matches!(
+        CarReader::new(&varint(1 << 40)[..]),
+        Err(CarError::SectionTooLarge { offset: 0, size }) if size == 1 << 40
+    ));
+    let car = header(1);
+    assert!(matches!(
+        CarReader::new(&car[..car.len() - 1]),
+        Err(CarError::UnexpectedEof { .. })
+    ));
+    assert!(matches!(
+        CarReader::new(&[0x01, 0xf6][..]),
+        Err(CarError::InvalidHeader(_))
+    ));
+    assert!(matches!(
+        CarReader::new(&header(2)[..]),
+        Err(CarError::UnsupportedVersion(2))
+    ));
+    assert!(matches!(
+        CarWriter::with_header(
+            Vec::new(),
+            &CarHeader {
+                version: 2,
+                roots: Vec::new()
+            }
+        ),
+        Err(CarError::UnsupportedVersion(2))
+    ));
+}
+
+#[test]
+fn test_invalid_sections() {
+    let start = header(1).len() as u64;
+
+    let mut car = header(1);
+    car.push(0x00);
+    assert!(matches!(
+        read_all(&car),
+        Err(CarError::EmptySection { offset }) if offset == start
+    ));
+
+    // The section ends in the middle of the CID.
+    let mut car = header(1);
+    car.extend(varint(4));
+    car.extend(&cid(1).to_bytes()[..4]);
+    assert!(matches!(
+        read_all(&car),
+        Err(CarError::InvalidCid { offset, .. }) if offset == start + 1
+    ));
+
+    // The data ends in the middle of the section.
+    let mut car = header(1);
+    car.extend(varint(100));
+    car.extend(cid(1).to_bytes());
+    assert!(matches!(
+        read_all(&car),
+        Err(CarError::UnexpectedEof { .. })
+    ));
+
+    let mut car = header(1);
+    car.extend(varint(100));
+    let reader = CarReader::new(&car[..]).unwrap().max_section_size(99);
+    assert!(matches!(
+        reader.collect::<Result<Vec<_>, _>>(),
+        Err(CarError::SectionTooLarge { offset, size: 100 }) if offset == start
+    ));
+}
+
+#[test]
+fn test_iteration_ends_after_error() {
+    let mut car = header(1);
+    car.push(0x00);
+    car.extend(varint(36));
+    car.extend(cid(1).to_bytes());
+    let mut reader = CarReader::new(&car[..]).unwrap();
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+}
+
+fn write_v2(blocks: &[(Cid, Vec<u8>)], index: Option<IndexCodec>) -> Cursor<Vec<u8>> {
+    let mut writer = CarV2Writer::new(Cursor::new(Vec::new()), vec![cid(1)])
+        .unwrap()
+        .index_codec(index);
+    for (cid, data) in blocks {
+        writer.write(cid, data).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+fn blocks() -> Vec<(Cid, Vec<u8>)> {
+    (1..=100)
+        .map(|index| (cid(index), vec![index; index as usize]))
+        .collect()
+}
+
+#[test]
+fn test_v2_roundtrip() {
+    for index in [
+        Some(IndexCodec::MultihashIndexSorted),
+        Some(IndexCodec::IndexSorted),
+        None,
+    ] {
+        let car = write_v2(&blocks(), index);
+        let mut reader = CarV2Reader::new(car).unwrap();
+        assert_eq!(reader.header(), &CarHeader::new(vec![cid(1)]));
+        let v2_header = *reader.v2_header().unwrap();
+        assert_eq!(v2_header.data_offset, 51);
+        assert_eq!(v2_header.index_offset != 0, index.is_some());
+        assert_eq!(v2_header.is_fully_indexed(), index.is_some());
+        for (cid, data) in blocks().iter().rev() {
+            assert_eq!(reader.get(cid).unwrap().as_ref(), Some(data));
+        }
+        assert_eq!(reader.get(&cid(101)).unwrap(), None);
+        assert_eq!(
+            reader
+                .blocks()
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap(),
+            blocks()
+        );
+    }
+}
+
+#[test]
+fn test_v2_layout() {
+    let car = write_v2(&[(cid(1), b"one".to_vec())], Some(IndexCodec::IndexSorted)).into_inner();
+    let mut v1 = CarWriter::new(Vec::new(), vec![cid(1)]).unwrap();
+    v1.write(&cid(1), b"one").unwrap();
+    let v1 = v1.finish().unwrap();
+    let header_len = header(1).len() as u64;
+
+    let mut expected = vec![0x0a, 0xa1, 0x67];
+    expected.extend(b"version");
+    expected.push(0x02);
+    // Characteristics with the fully indexed bit, data offset and size, index offset.
+    expected.push(0x80);
+    expected.extend([0; 15]);
+    expected.extend(51u64.to_le_bytes());
+    expected.extend((v1.len() as u64).to_le_bytes());
+    expected.extend((51 + v1.len() as u64).to_le_bytes());
+    expected.extend(&v1);
+    // The index codec, one bucket with a single record of a 32 bytes digest.
+    expected.extend([0x80, 0x08]);
+    expected.extend(1i32.to_le_bytes());
+    expected.extend(40u32.to_le_bytes());
+    expected.extend(40u64.to_le_bytes());
+    expected.extend([1; 32]);
+    expected.extend(header_len.to_le_bytes());
+    assert_eq!(car, expected);
+}
+
+#[test]
+fn test_v2_shared_multihash() {
+    // Both CIDs have the same multihash, which is all the index records.
+    let raw = cid(1);
+    let dag_cbor = Cid::new_v1(0x71, *raw.hash());
+    let blocks = vec![(raw, b"raw".to_vec()), (dag_cbor, b"dag-cbor".to_vec())];
+    for index in [IndexCodec::MultihashIndexSorted, IndexCodec::IndexSorted] {
+        let mut reader = CarV2Reader::new(write_v2(&blocks, Some(index))).unwrap();
+        assert_eq!(reader.get(&dag_cbor).unwrap(), Some(b"dag-cbor".to_vec()));
+        assert_eq!(reader.get(&raw).unwrap(), Some(b"raw".to_vec()));
+        let other = Cid::new_v1(0x70, *raw.hash());
+        assert_eq!(reader.get(&other).unwrap(), None);
+    }
+}
+
+#[test]
+fn test_v2_reader_indexes_v1() {
+    let mut writer = CarWriter::new(Vec::new(), vec![cid(1)]).unwrap();
+    for (cid, data) in blocks() {
+        writer.write(&cid, &data).unwrap();
+    }
+    let car = writer.finish().unwrap();
+    let mut reader = CarV2Reader::new(Cursor::new(car)).unwrap();
+    assert!(reader.v2_header().is_none());
+    assert_eq!(reader.get(&cid(42)).unwrap(), Some(vec![42; 42]));
+    assert_eq!(reader.get(&cid(101)).unwrap(), None);
+}
+
+#[test]
+fn test_v2_invalid() {
+    let car = write_v2(&blocks(), Some(IndexCodec::MultihashIndexSorted)).into_inner();
+    let index_offset = u64::from_le_bytes(car[43..51].try_into().unwrap()) as usize;
+
+    assert!(matches!(
+        CarReader::new(&car[..]),
+        Err(CarError::InvalidHeader(_))
+    ));
+
+    let mut invalid = car.clone();
+    invalid[10] = 3;
+    assert!(matches!(
+        CarV2Reader::new(Cursor::new(invalid)),
+        Err(CarError::UnsupportedVersion(3))
+    ));
+
+    // The data offset points into the header.
+    let mut invalid = car.clone();
+    invalid[27..35].copy_from_slice(&10u64.to_le_bytes());
+    assert!(matches!(
+        CarV2Reader::new(Cursor::new(invalid)),
+        Err(CarError::InvalidHeader(_))
+    ));
+
+    // The index offset points into the data payload.
+    let mut invalid = car.clone();
+    invalid[43..51].copy_from_slice(&60u64.to_le_bytes());
+    assert!(matches!(
+        CarV2Reader::new(Cursor::new(invalid)),
+        Err(CarError::InvalidHeader(_))
+    ));
+
+    let mut invalid = car.clone();
+    invalid[index_offset] = 0x82;
+    assert!(matches!(
+        CarV2Reader::new(Cursor::new(invalid)),
+        Err(CarError::UnsupportedIndex(0x0402))
+    ));
+
+    assert!(matches!(
+        CarV2Reader::new(Cursor::new(&car[..car.len() - 1])),
+        Err(CarError::UnexpectedEof { .. })
+    ));
+
+    // The records of the bucket are not sorted.
+    let mut invalid = car.clone();
+    let records = index_offset + 2 + 4 + 8 + 4 + 4 + 8;
+    invalid[records..records + 32].copy_from_slice(&[0xff; 32]);
+    assert!(matches!(
+        CarV2Reader::new(Cursor::new(invalid)),
+        Err(CarError::InvalidIndex(_))
+    ));
+
+    // The bucket size isn't a multiple of the width.
+    let mut invalid = car.clone();
+    let size = index_offset + 2 + 4 + 8 + 4 + 4;
+    invalid[size] += 1;
+    assert!(matches!(
+        CarV2Reader::new(Cursor::new(invalid)),
+        Err(CarError::InvalidIndex(_))
+    ));
+}
+
+#[cfg(feature = "codec")]
+#[test]
+fn test_v2_block_loader() {
+    use ipld_core::{ipld::Ipld, traversal::Traversal};
+    use serde_ipld_dagcbor::codec::DagCborCodec;
+
+    let leaf = serde_ipld_dagcbor::to_vec(&ipld!("leaf")).unwrap();
+    let leaf_cid = Cid::new_v1(0x71, Multihash::wrap(0x12, &[1; 32]).unwrap());
+    let root = serde_ipld_dagcbor::to_vec(&ipld!({"leaf": leaf_cid})).unwrap();
+    let root_cid = Cid::new_v1(0x71, Multihash::wrap(0x12, &[2; 32]).unwrap());
+    let car = write_v2(
+        &[(root_cid, root), (leaf_cid, leaf)],
+        Some(IndexCodec::MultihashIndexSorted),
+    );
+
+    let reader = CarV2Reader::new(car).unwrap();
+    let traversal = Traversal::new(reader).with_codec::<DagCborCodec>();
+    // The CIDs are made up, hence the hashes cannot be verified.
+    #[cfg(feature = "hash")]
+    let traversal = traversal.verify_hashes(false);
+    let mut traversal = traversal;
+    let mut visited = Vec::new();
+    traversal
+        .walk(&root_cid, |path, node| {
+            visited.push((path.to_string(), node.clone()))
+        })
+        .unwrap();
+    assert_eq!(
+        visited[1],
+        ("/leaf".to_string(), Ipld::String("leaf".into()))
+    );
+}
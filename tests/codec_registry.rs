@@ -0,0 +1,110 @@
+#![cfg(all(feature = "std", feature = "codec"))]
+
+use ipld_core::{
+    cid::{multihash::Multihash, Cid},
+    codec::{CodecRegistry, CodecRegistryError, DynCodec, DynCodecError},
+    ipld,
+    ipld::Ipld,
+};
+use serde_ipld_dagcbor::codec::DagCborCodec;
+use serde_ipld_dagjson::codec::DagJsonCodec;
+
+fn cid(codec: u64) -> Cid {
+    Cid::new_v1(codec, Multihash::wrap(0x12, &[0; 32]).unwrap())
+}
+
+fn registry() -> CodecRegistry {
+    CodecRegistry::new()
+        .with_codec::<DagCborCodec>()
+        .with_codec::<DagJsonCodec>()
+}
+
+#[test]
+fn test_dispatch_by_cid() {
+    let registry = registry();
+    let link = cid(0x55);
+    let ipld = ipld!({"name": "block", "link": link});
+    for code in [0x71, 0x0129] {
+        let bytes = registry.encode(code, &ipld).unwrap();
+        assert_eq!(registry.decode_block(&cid(code), &bytes).unwrap(), ipld);
+        assert_eq!(registry.links(&cid(code), &bytes).unwrap(), [link]);
+    }
+    let json = registry.encode(0x0129, &ipld!([1])).unwrap();
+    assert_eq!(json, b"[1]");
+    assert_eq!(registry.codes().collect::<Vec<_>>(), [0x71, 0x0129]);
+}
+
+#[test]
+fn test_errors() {
+    let registry = registry();
+    assert!(matches!(
+        registry.decode_block(&cid(0x55), b"data"),
+        Err(CodecRegistryError::UnknownCodec(0x55))
+    ));
+    assert!(matches!(
+        registry.encode(0x55, &ipld!(null)),
+        Err(CodecRegistryError::UnknownCodec(0x55))
+    ));
+    assert!(matches!(
+        registry.decode_block(&cid(0x71), &[0xff]),
+        Err(CodecRegistryError::Codec { code: 0x71, .. })
+    ));
+    assert!(matches!(
+        registry.links(&cid(0x0129), b"{"),
+        Err(CodecRegistryError::Codec { code: 0x0129, .. })
+    ));
+}
+
+#[test]
+fn test_inline_identity_block() {
+    let registry = registry();
+    let link = cid(0x55);
+    let bytes = registry.encode(0x71, &ipld!([link])).unwrap();
+    let inline = Cid::new_v1(0x71, Multihash::wrap(0x00, &bytes).unwrap());
+    assert_eq!(registry.decode_block(&inline, &[]).unwrap(), ipld!([link]));
+    assert_eq!(registry.links(&inline, &[]).unwrap(), [link]);
+}
+
+/// A codec that is only known at runtime, it stores a string as bytes.
+struct Utf8Codec(u64);
+
+impl DynCodec for Utf8Codec {
+    fn code(&self) -> u64 {
+        self.0
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Ipld, DynCodecError> {
+        Ok(Ipld::String(String::from_utf8(bytes.to_vec())?))
+    }
+
+    fn encode(&self, ipld: &Ipld) -> Result<Vec<u8>, DynCodecError> {
+        match ipld {
+            Ipld::String(string) => Ok(string.as_bytes().to_vec()),
+            _ => Err("only strings are supported".into()),
+        }
+    }
+
+    fn links(&self, _bytes: &[u8]) -> Result<Vec<Cid>, DynCodecError> {
+        Ok(Vec::new())
+    }
+}
+
+#[test]
+fn test_register_dyn_codec() {
+    let mut registry = registry();
+    assert!(registry.register(Box::new(Utf8Codec(0x300000))).is_none());
+    assert!(registry.contains(0x300000));
+    assert_eq!(
+        registry.decode_block(&cid(0x300000), b"hello").unwrap(),
+        ipld!("hello")
+    );
+    assert!(registry.encode(0x300000, &ipld!(1)).is_err());
+
+    // Registering a codec with the same code replaces it.
+    let replaced = registry.register(Box::new(Utf8Codec(0x71))).unwrap();
+    assert_eq!(replaced.code(), 0x71);
+    assert_eq!(
+        registry.decode_block(&cid(0x71), b"hello").unwrap(),
+        ipld!("hello")
+    );
+}
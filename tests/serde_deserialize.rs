@@ -2,13 +2,19 @@
 
 extern crate alloc;
 
-use alloc::collections::BTreeMap;
+use alloc::{borrow::Cow, collections::BTreeMap};
 use core::convert::TryFrom;
 
+use serde::de::DeserializeSeed;
+use serde_ipld_dagcbor::codec::DagCborCodec;
+use serde_ipld_dagjson::codec::DagJsonCodec;
 use serde_test::{assert_de_tokens, Token};
 
 use ipld_core::cid::{serde::CID_SERDE_PRIVATE_IDENTIFIER, Cid};
-use ipld_core::ipld::Ipld;
+use ipld_core::codec::Codec;
+use ipld_core::ipld;
+use ipld_core::ipld::{Ipld, IpldRef, SharedIpld};
+use ipld_core::serde::{from_ipld, IpldDeserializeOptions};
 
 #[test]
 fn ipld_deserialize_null() {
@@ -153,3 +159,134 @@ fn ipld_deserialize_link_not_as_bytes() {
         ])],
     );
 }
+
+fn deserialize_with_options(bytes: &[u8], options: IpldDeserializeOptions) -> Result<Ipld, String> {
+    options
+        .deserialize(&mut serde_ipld_dagcbor::de::Deserializer::from_slice(bytes))
+        .map_err(|error| error.to_string())
+}
+
+#[test]
+fn ipld_deserialize_options() {
+    let cid = Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+    let ipld = ipld!({"list": [1, "two", [null, true]], "link": cid, "bytes": Ipld::Bytes(vec![1, 2, 3]), "float": 1.5});
+    let bytes = DagCborCodec::encode_to_vec(&ipld).unwrap();
+    assert_eq!(
+        deserialize_with_options(&bytes, IpldDeserializeOptions::new()).unwrap(),
+        ipld
+    );
+    let options = IpldDeserializeOptions::new()
+        .max_depth(3)
+        .max_nodes(10)
+        .max_string_len(5)
+        .max_bytes_len(3);
+    assert_eq!(deserialize_with_options(&bytes, options).unwrap(), ipld);
+
+    // The options work with any deserializer.
+    let json = DagJsonCodec::encode_to_vec(&ipld).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_slice(&json);
+    let from_json = IpldDeserializeOptions::new()
+        .deserialize(&mut deserializer)
+        .unwrap();
+    assert_eq!(from_json.get("list").unwrap(), ipld.get("list").unwrap());
+}
+
+#[test]
+fn ipld_deserialize_options_limits() {
+    let ipld = ipld!({"list": [1, "two", [null, true]], "bytes": Ipld::Bytes(vec![1, 2, 3])});
+    let bytes = DagCborCodec::encode_to_vec(&ipld).unwrap();
+    let error = deserialize_with_options(&bytes, IpldDeserializeOptions::new().max_depth(2));
+    assert!(error.unwrap_err().contains("maximum depth of 2 exceeded"));
+    let error = deserialize_with_options(&bytes, IpldDeserializeOptions::new().max_nodes(7));
+    assert!(error
+        .unwrap_err()
+        .contains("maximum number of 7 nodes exceeded"));
+    let error = deserialize_with_options(&bytes, IpldDeserializeOptions::new().max_string_len(3));
+    assert!(error
+        .unwrap_err()
+        .contains("maximum string length of 3 exceeded"));
+    let error = deserialize_with_options(&bytes, IpldDeserializeOptions::new().max_bytes_len(2));
+    assert!(error
+        .unwrap_err()
+        .contains("maximum bytes length of 2 exceeded"));
+
+    // Map keys count as strings.
+    let bytes = DagCborCodec::encode_to_vec(&ipld!({"long key": 1})).unwrap();
+    let error = deserialize_with_options(&bytes, IpldDeserializeOptions::new().max_string_len(3));
+    assert!(error
+        .unwrap_err()
+        .contains("maximum string length of 3 exceeded"));
+}
+
+#[test]
+fn ipld_deserialize_options_deeply_nested() {
+    // Such deeply nested data would overflow the stack without a depth limit.
+    let mut bytes = vec![0x81; 100_000];
+    bytes.push(0x01);
+    let error = deserialize_with_options(&bytes, IpldDeserializeOptions::new().max_depth(64));
+    assert!(error.unwrap_err().contains("maximum depth of 64 exceeded"));
+}
+
+#[test]
+fn ipld_deserialize_bounds_depth() {
+    // The nesting depth is bounded by default, so that the returned value can be dropped.
+    let nested = |depth| (0..depth).fold(Ipld::Null, |ipld, _| Ipld::List(vec![ipld]));
+    let max = IpldDeserializeOptions::DEFAULT_MAX_DEPTH;
+    assert_eq!(from_ipld::<Ipld>(nested(max)).unwrap(), nested(max));
+    let error = from_ipld::<Ipld>(nested(max + 1)).unwrap_err();
+    assert!(error.to_string().contains("maximum depth of 128 exceeded"));
+
+    let mut bytes = vec![0x81; 100_000];
+    bytes.push(0x01);
+    assert!(serde_ipld_dagcbor::from_slice::<Ipld>(&bytes).is_err());
+}
+
+#[test]
+fn ipld_ref_deserialize_borrowed() {
+    let cid = Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+    let ipld = ipld!({"string": "value", "bytes": Ipld::Bytes(vec![1, 2]), "list": [1, -2.5, null, true, cid]});
+    let bytes = DagCborCodec::encode_to_vec(&ipld).unwrap();
+    let borrowed: IpldRef = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+    assert!(matches!(
+        borrowed.get("string").unwrap(),
+        Some(IpldRef::String(Cow::Borrowed("value")))
+    ));
+    assert!(matches!(
+        borrowed.get("bytes").unwrap(),
+        Some(IpldRef::Bytes(Cow::Borrowed([1, 2])))
+    ));
+    let IpldRef::Map(map) = &borrowed else {
+        panic!("expected a map");
+    };
+    assert!(map.keys().all(|key| matches!(key, Cow::Borrowed(_))));
+    assert_eq!(borrowed.to_owned(), ipld);
+}
+
+#[test]
+fn ipld_ref_deserialize_owned() {
+    // Strings with escape sequences cannot be borrowed from JSON.
+    let json = br#"{"escaped\n": "new\nline", "plain": "text"}"#;
+    let borrowed: IpldRef = serde_ipld_dagjson::from_slice(json).unwrap();
+    assert!(matches!(
+        borrowed.get("escaped\n").unwrap(),
+        Some(IpldRef::String(Cow::Owned(string))) if string == "new\nline"
+    ));
+    assert!(matches!(
+        borrowed.get("plain").unwrap(),
+        Some(IpldRef::String(Cow::Borrowed("text")))
+    ));
+    assert_eq!(
+        borrowed.into_owned(),
+        ipld!({"escaped\n": "new\nline", "plain": "text"})
+    );
+}
+
+#[test]
+fn shared_ipld_roundtrip() {
+    let ipld = ipld!({"list": [1, "two"], "bytes": Ipld::Bytes(vec![3])});
+    let shared = SharedIpld::from(ipld.clone());
+    let bytes = DagCborCodec::encode_to_vec(&shared).unwrap();
+    assert_eq!(bytes, DagCborCodec::encode_to_vec(&ipld).unwrap());
+    let decoded: SharedIpld = DagCborCodec::decode_from_slice(&bytes).unwrap();
+    assert_eq!(decoded, shared);
+}
@@ -11,6 +11,7 @@ use serde_json::json;
 
 use ipld_core::cid::Cid;
 use ipld_core::ipld::Ipld;
+use ipld_core::serde::{from_ipld, from_ipld_ref, SerdeErrorKind};
 
 /// This function is to test that all IPLD kinds except the given one errors, when trying to
 /// deserialize to the given Rust type.
@@ -735,3 +736,246 @@ fn ipld_deserializer_serde_json_value_cid_fails() {
     let error = serde_json::Value::deserialize(ipld);
     assert!(error.is_err());
 }
+
+/// Deserializing from a reference borrows strings and bytes from the input.
+#[test]
+fn ipld_deserializer_ref_borrowed() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        data: &'a [u8],
+        link: Cid,
+        list: Vec<u8>,
+    }
+
+    let cid = Cid::try_from("bafkreie74tgmnxqwojhtumgh5dzfj46gi4mynlfr7dmm7duwzyvnpw7h7m").unwrap();
+    let ipld = Ipld::Map(BTreeMap::from([
+        ("name".into(), Ipld::String("hello".into())),
+        ("data".into(), Ipld::Bytes(vec![0x01, 0x02])),
+        ("link".into(), Ipld::Link(cid)),
+        (
+            "list".into(),
+            Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2)]),
+        ),
+    ]));
+
+    let deserialized: Borrowed = from_ipld_ref(&ipld).unwrap();
+    assert_eq!(
+        deserialized,
+        Borrowed {
+            name: "hello",
+            data: &[0x01, 0x02],
+            link: cid,
+            list: vec![1, 2],
+        }
+    );
+    let Ipld::Map(map) = &ipld else {
+        unreachable!()
+    };
+    let Some(Ipld::String(name)) = map.get("name") else {
+        unreachable!()
+    };
+    assert_eq!(deserialized.name.as_ptr(), name.as_ptr());
+}
+
+/// Deserializing from a reference gives the same result as deserializing the owned value.
+#[test]
+fn ipld_deserializer_ref_matches_owned() {
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Line(u8, u8),
+        Rect { width: u16, height: u16 },
+    }
+
+    let ipld = Ipld::List(vec![
+        Ipld::String("Point".into()),
+        Ipld::Map(BTreeMap::from([("Circle".into(), Ipld::Float(1.5))])),
+        Ipld::Map(BTreeMap::from([(
+            "Line".into(),
+            Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2)]),
+        )])),
+        Ipld::Map(BTreeMap::from([(
+            "Rect".into(),
+            Ipld::Map(BTreeMap::from([
+                ("width".into(), Ipld::Integer(3)),
+                ("height".into(), Ipld::Integer(4)),
+            ])),
+        )])),
+    ]);
+
+    let expected = vec![
+        Shape::Point,
+        Shape::Circle(1.5),
+        Shape::Line(1, 2),
+        Shape::Rect {
+            width: 3,
+            height: 4,
+        },
+    ];
+    assert_eq!(from_ipld_ref::<Vec<Shape>>(&ipld).unwrap(), expected);
+    assert_eq!(from_ipld::<Vec<Shape>>(ipld.clone()).unwrap(), expected);
+    assert_eq!(from_ipld_ref::<Ipld>(&ipld).unwrap(), ipld);
+
+    assert!(from_ipld_ref::<u8>(&Ipld::Integer(256)).is_err());
+    assert!(from_ipld_ref::<f32>(&Ipld::Float(0.1)).is_err());
+    assert!(from_ipld_ref::<(u8,)>(&Ipld::List(vec![Ipld::Integer(1), Ipld::Integer(2)])).is_err());
+    assert!(from_ipld_ref::<Cid>(&Ipld::Bytes(vec![0x01])).is_err());
+}
+
+/// Errors contain the kind of the failure and the path to the value that caused it.
+#[test]
+fn ipld_deserializer_error_path() {
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Inner {
+        value: u8,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    enum Wrapper {
+        Inner(Inner),
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Outer {
+        items: Vec<Inner>,
+        wrapper: Option<Wrapper>,
+    }
+
+    fn outer(items: Vec<Ipld>, wrapper: Ipld) -> Ipld {
+        Ipld::Map(BTreeMap::from([
+            ("items".into(), Ipld::List(items)),
+            ("wrapper".into(), wrapper),
+        ]))
+    }
+    fn inner(entries: Vec<(&str, Ipld)>) -> Ipld {
+        Ipld::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    let valid = inner(vec![("value", Ipld::Integer(1))]);
+    let cases = [
+        (
+            outer(
+                vec![valid.clone(), inner(vec![("value", Ipld::Integer(256))])],
+                Ipld::Null,
+            ),
+            SerdeErrorKind::OutOfRange,
+            "/items/1/value",
+        ),
+        (
+            outer(vec![inner(vec![("value", Ipld::Bool(true))])], Ipld::Null),
+            SerdeErrorKind::WrongKind,
+            "/items/0/value",
+        ),
+        (
+            outer(vec![inner(vec![])], Ipld::Null),
+            SerdeErrorKind::MissingField,
+            "/items/0",
+        ),
+        (
+            outer(
+                vec![inner(vec![
+                    ("value", Ipld::Integer(1)),
+                    ("unknown", Ipld::Null),
+                ])],
+                Ipld::Null,
+            ),
+            SerdeErrorKind::UnknownField,
+            "/items/0/unknown",
+        ),
+        (
+            outer(vec![], inner(vec![("Unknown", valid.clone())])),
+            SerdeErrorKind::UnknownVariant,
+            "/wrapper/Unknown",
+        ),
+        (
+            outer(
+                vec![],
+                inner(vec![(
+                    "Inner",
+                    inner(vec![("value", Ipld::String("1".into()))]),
+                )]),
+            ),
+            SerdeErrorKind::WrongKind,
+            "/wrapper/Inner/value",
+        ),
+        (Ipld::List(vec![]), SerdeErrorKind::WrongKind, ""),
+    ];
+
+    for (ipld, kind, path) in cases {
+        let owned = from_ipld::<Outer>(ipld.clone()).unwrap_err();
+        let borrowed = from_ipld_ref::<Outer>(&ipld).unwrap_err();
+        for error in [owned, borrowed] {
+            assert_eq!(error.kind(), kind, "{}", error);
+            assert_eq!(error.path().to_string(), path, "{}", error);
+        }
+    }
+
+    let error = from_ipld::<Outer>(outer(
+        vec![inner(vec![("value", Ipld::Bool(true))])],
+        Ipld::Null,
+    ))
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "serde error at `/items/0/value`: Only `Ipld::Integer` can be deserialized to `u8`, input was `true`"
+    );
+}
+
+/// Map keys can be deserialized into any type that a String can be deserialized into.
+#[test]
+fn ipld_deserializer_map_keys() {
+    #[derive(Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+    struct Name(String);
+
+    #[derive(Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+    enum Color {
+        Red,
+        Blue,
+    }
+
+    let ipld = Ipld::Map(BTreeMap::from([
+        ("Blue".into(), Ipld::Integer(1)),
+        ("Red".into(), Ipld::Integer(2)),
+    ]));
+    let names: BTreeMap<Name, u8> = from_ipld(ipld.clone()).unwrap();
+    assert_eq!(names[&Name("Red".into())], 2);
+    let colors: BTreeMap<Color, u8> = from_ipld(ipld.clone()).unwrap();
+    assert_eq!(colors[&Color::Blue], 1);
+    let chars = from_ipld::<BTreeMap<char, u8>>(ipld).unwrap_err();
+    assert_eq!(chars.kind(), SerdeErrorKind::InvalidValue);
+    assert_eq!(chars.path().to_string(), "/Blue");
+}
+
+/// Values that aren't deserialized are dropped without overflowing the stack.
+#[test]
+fn ipld_deserializer_drops_deeply_nested() {
+    let nested = || (0..100_000).fold(Ipld::Null, |ipld, _| Ipld::List(vec![ipld]));
+    assert!(from_ipld::<u8>(nested()).is_err());
+    assert!(from_ipld::<Vec<u8>>(Ipld::List(vec![Ipld::Integer(1), nested()])).is_err());
+    let ipld = Ipld::Map(BTreeMap::from([("ignored".into(), nested())]));
+    assert!(from_ipld::<BTreeMap<String, serde::de::IgnoredAny>>(ipld).is_ok());
+}
+
+/// Values are truncated in error messages.
+#[test]
+fn ipld_deserializer_error_preview() {
+    let ipld = Ipld::List((0..100).map(Ipld::Integer).collect());
+    let error = from_ipld::<String>(ipld).unwrap_err();
+    assert_eq!(error.kind(), SerdeErrorKind::WrongKind);
+    assert!(error.path().is_empty());
+    assert_eq!(
+        error.message(),
+        "Only `Ipld::String` can be deserialized to string, input was `[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 1...`"
+    );
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "serde")]
+
+use std::convert::Infallible;
+
+use serde::Deserialize;
+
+use ipld_core::{
+    cid::{multihash::Multihash, Cid},
+    codec::Codec,
+    ipld,
+    ipld::Ipld,
+    serde::{ExtractLinkPaths, ExtractLinks, ExtractLinksError, LinkLimits},
+};
+use serde_ipld_dagcbor::{codec::DagCborCodec, de::Deserializer, DecodeError};
+
+fn cid(digest: u8) -> Cid {
+    Cid::new_v1(0x71, Multihash::wrap(0x12, &[digest; 32]).unwrap())
+}
+
+#[test]
+fn test_extract_link_paths() {
+    let (a, b) = (cid(1), cid(2));
+    let ipld = ipld!({
+        "children": [{"cid": a}, {"cid": b}, {"name": "none"}, {"cid": a}],
+        "self": b,
+        "nested": {"list": [[a]]},
+    });
+    let bytes = DagCborCodec::encode_to_vec(&ipld).unwrap();
+    let extracted: ExtractLinkPaths<64> = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+    let paths: Vec<_> = extracted
+        .links()
+        .iter()
+        .map(|(path, cid)| (path.to_string(), *cid))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("/self".to_string(), b),
+            ("/nested/list/0/0".to_string(), a),
+            ("/children/0/cid".to_string(), a),
+            ("/children/1/cid".to_string(), b),
+            ("/children/3/cid".to_string(), a),
+        ]
+    );
+    assert_eq!(
+        extracted.counts().into_iter().collect::<Vec<_>>(),
+        [(a, 3), (b, 2)]
+    );
+
+    // The plain links are in the same order.
+    let links: ExtractLinks<64> = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+    assert_eq!(links.into_vec(), [b, a, a, b, a]);
+}
+
+#[test]
+fn test_extract_link_paths_root() {
+    let bytes = DagCborCodec::encode_to_vec(&ipld!(cid(1))).unwrap();
+    let extracted: ExtractLinkPaths<64> = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+    assert_eq!(extracted.into_vec(), [(Default::default(), cid(1))]);
+
+    let bytes = DagCborCodec::encode_to_vec(&ipld!([1, "two", null])).unwrap();
+    let extracted: ExtractLinkPaths<64> = serde_ipld_dagcbor::from_slice(&bytes).unwrap();
+    assert!(extracted.links().is_empty());
+}
+
+#[test]
+fn test_extract_link_paths_max_depth() {
+    // DAG-CBOR has a lower depth limit, hence deserialize from `Ipld`.
+    let nested = |depth| (0..depth).fold(ipld!(1), |ipld, _| Ipld::List(vec![ipld]));
+    assert!(ExtractLinkPaths::<64>::deserialize(nested(LinkLimits::DEFAULT_MAX_DEPTH)).is_ok());
+
+    let error =
+        ExtractLinkPaths::<64>::deserialize(nested(LinkLimits::DEFAULT_MAX_DEPTH + 1)).unwrap_err();
+    assert!(error.to_string().ends_with("maximum depth of 128 exceeded"));
+}
+
+fn extract_bounded(
+    ipld: &Ipld,
+    limits: &LinkLimits,
+) -> Result<Vec<Cid>, ExtractLinksError<DecodeError<Infallible>>> {
+    let bytes = DagCborCodec::encode_to_vec(ipld).unwrap();
+    let mut deserializer = Deserializer::from_slice(&bytes);
+    ExtractLinks::<64>::deserialize_bounded(&mut deserializer, limits).map(ExtractLinks::into_vec)
+}
+
+#[test]
+fn test_extract_links_bounded() {
+    let (a, b) = (cid(1), cid(2));
+    let ipld = ipld!({"list": [a, [b]], "map": {"a": a}});
+    // DAG-CBOR sorts the keys by length first.
+    let links = extract_bounded(&ipld, &LinkLimits::new()).unwrap();
+    assert_eq!(links, [a, a, b]);
+
+    let limits = LinkLimits::new()
+        .max_depth(3)
+        .max_links(3)
+        .max_collection_len(2);
+    assert_eq!(extract_bounded(&ipld, &limits).unwrap(), [a, a, b]);
+}
+
+#[test]
+fn test_extract_links_limits() {
+    let (a, b) = (cid(1), cid(2));
+    let ipld = ipld!({"list": [a, [b]], "map": {"a": a}});
+    assert!(matches!(
+        extract_bounded(&ipld, &LinkLimits::new().max_depth(2)),
+        Err(ExtractLinksError::MaxDepth(2))
+    ));
+    assert!(matches!(
+        extract_bounded(&ipld, &LinkLimits::new().max_links(2)),
+        Err(ExtractLinksError::MaxLinks(2))
+    ));
+    assert!(matches!(
+        extract_bounded(&ipld, &LinkLimits::new().max_collection_len(1)),
+        Err(ExtractLinksError::MaxCollectionLen(1))
+    ));
+
+    // Deeply nested input doesn't overflow the stack.
+    // The bytes are created by hand, as such a nested `Ipld` would overflow the stack itself.
+    let mut bytes = vec![0x81; 100_000];
+    bytes.extend(DagCborCodec::encode_to_vec(&ipld!(a)).unwrap());
+    let mut deserializer = Deserializer::from_slice(&bytes);
+    assert!(matches!(
+        ExtractLinks::<64>::deserialize_bounded(
+            &mut deserializer,
+            &LinkLimits::new().max_depth(64)
+        ),
+        Err(ExtractLinksError::MaxDepth(64))
+    ));
+}
+
+#[test]
+fn test_extract_links_deserialize_error() {
+    let mut deserializer = Deserializer::from_slice(&[0x82, 0x01]);
+    assert!(matches!(
+        ExtractLinks::<64>::deserialize_bounded(&mut deserializer, &LinkLimits::new()),
+        Err(ExtractLinksError::Deserialize(_))
+    ));
+}
@@ -0,0 +1,287 @@
+#![cfg(all(feature = "std", feature = "codec"))]
+
+use std::collections::BTreeMap;
+
+use ipld_core::{
+    blockstore::{Blockstore, MemoryBlockstore},
+    cid::{multihash::Multihash, Cid},
+    codec::{Codec, RawCodec},
+    ipld,
+    ipld::Ipld,
+    selector::Selector,
+    traversal::{BlockLoader, BlockNotFound, Traversal, TraversalError},
+};
+use serde_ipld_dagcbor::codec::DagCborCodec;
+
+/// A block loader that records which blocks were loaded.
+#[derive(Default)]
+struct Blocks {
+    blocks: BTreeMap<Cid, Vec<u8>>,
+    loaded: Vec<Cid>,
+}
+
+impl Blocks {
+    /// Adds a block.
+    fn add(&mut self, ipld: &Ipld) -> Cid {
+        let data = DagCborCodec::encode_to_vec(ipld).unwrap();
+        let cid = cid_of(
+            <DagCborCodec as Codec<Ipld>>::CODE,
+            &data,
+            self.blocks.len() as u8,
+        );
+        self.blocks.insert(cid, data);
+        cid
+    }
+}
+
+impl BlockLoader for Blocks {
+    type Error = BlockNotFound;
+
+    fn load(&mut self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+        self.loaded.push(*cid);
+        self.blocks.load(cid)
+    }
+}
+
+/// Returns the CID of the data encoded with the given codec.
+///
+/// Without the `hash` feature the CID is made up from the seed, as traversals only verify hashes
+/// if it's enabled.
+fn cid_of(codec: u64, data: &[u8], seed: u8) -> Cid {
+    #[cfg(feature = "hash")]
+    {
+        let _ = seed;
+        ipld_core::hash::cid_v1(codec, ipld_core::hash::Code::Sha2_256, data).unwrap()
+    }
+    #[cfg(not(feature = "hash"))]
+    {
+        let _ = data;
+        Cid::new_v1(codec, Multihash::wrap(0x12, &[seed; 32]).unwrap())
+    }
+}
+
+/// Creates a chain of blocks, each one linking to the previous one. Returns the CIDs of the
+/// blocks, the last one is the head of the chain.
+fn chain(blocks: &mut Blocks, len: usize) -> Vec<Cid> {
+    let mut cids = vec![blocks.add(&ipld!({"index": 0}))];
+    for index in 1..len {
+        let previous = *cids.last().unwrap();
+        cids.push(blocks.add(&ipld!({"index": index, "previous": previous})));
+    }
+    cids
+}
+
+fn walk<L: BlockLoader>(
+    traversal: &mut Traversal<L>,
+    root: &Cid,
+) -> Result<Vec<(String, Ipld)>, TraversalError<L::Error>> {
+    let mut visited = Vec::new();
+    traversal.walk(root, |path, node| {
+        visited.push((path.to_string(), node.clone()))
+    })?;
+    Ok(visited)
+}
+
+#[test]
+fn test_walk() {
+    let mut blocks = Blocks::default();
+    let leaf = blocks.add(&ipld!("leaf"));
+    let root = blocks.add(&ipld!({"list": [1, leaf], "leaf": leaf}));
+
+    let mut traversal = Traversal::new(blocks).with_codec::<DagCborCodec>();
+    let visited = walk(&mut traversal, &root).unwrap();
+    let paths: Vec<_> = visited.iter().map(|(path, _)| path.as_str()).collect();
+    assert_eq!(paths, ["", "/leaf", "/list", "/list/0", "/list/1"]);
+    assert_eq!(visited[1].1, ipld!("leaf"));
+    assert_eq!(visited[4].1, ipld!("leaf"));
+}
+
+#[test]
+fn test_select_loads_only_explored_blocks() {
+    let mut blocks = Blocks::default();
+    let cids = chain(&mut blocks, 5);
+    let head = *cids.last().unwrap();
+    let unrelated = blocks.add(&ipld!("unrelated"));
+    let root = blocks.add(&ipld!({"chain": head, "other": unrelated}));
+
+    // Select the index of the head of the chain and the one before.
+    let selector = Selector::try_from(ipld!({"f": {"f>": {"chain": {"R": {
+        "l": {"depth": 2},
+        ":>": {"f": {"f>": {"index": {".": {}}, "previous": {"@": {}}}}},
+    }}}}}))
+    .unwrap();
+    let mut traversal = Traversal::new(blocks).with_codec::<DagCborCodec>();
+    let mut selected = Vec::new();
+    traversal
+        .select(&root, &selector, |path, node| {
+            selected.push((path.to_string(), node.clone()))
+        })
+        .unwrap();
+    assert_eq!(
+        selected,
+        [
+            ("/chain/index".to_string(), ipld!(4)),
+            ("/chain/previous/index".to_string(), ipld!(3)),
+        ]
+    );
+    assert_eq!(traversal.into_loader().loaded, [root, cids[4], cids[3]]);
+}
+
+#[test]
+fn test_max_blocks() {
+    let mut blocks = Blocks::default();
+    let cids = chain(&mut blocks, 5);
+    let head = *cids.last().unwrap();
+
+    let mut traversal = Traversal::new(blocks)
+        .with_codec::<DagCborCodec>()
+        .max_blocks(5);
+    assert!(walk(&mut traversal, &head).is_ok());
+
+    let mut traversal = traversal.max_blocks(4);
+    assert!(matches!(
+        walk(&mut traversal, &head),
+        Err(TraversalError::MaxBlocks(4))
+    ));
+}
+
+#[test]
+fn test_max_depth() {
+    let mut blocks = Blocks::default();
+    let cids = chain(&mut blocks, 5);
+    let head = *cids.last().unwrap();
+
+    let mut traversal = Traversal::new(blocks)
+        .with_codec::<DagCborCodec>()
+        .max_depth(4);
+    assert!(walk(&mut traversal, &head).is_ok());
+
+    let mut traversal = traversal.max_depth(3);
+    assert!(matches!(
+        walk(&mut traversal, &head),
+        Err(TraversalError::MaxDepth(3))
+    ));
+}
+
+#[test]
+fn test_errors() {
+    let mut blocks = Blocks::default();
+    let missing = cid_of(<DagCborCodec as Codec<Ipld>>::CODE, &[0xff], 0xff);
+    let root = blocks.add(&ipld!({"missing": missing}));
+
+    let mut traversal = Traversal::new(blocks);
+    assert!(matches!(
+        walk(&mut traversal, &root),
+        Err(TraversalError::UnknownCodec { cid }) if cid == root
+    ));
+
+    let mut traversal = Traversal::new(traversal.into_loader()).with_codec::<DagCborCodec>();
+    assert!(matches!(
+        walk(&mut traversal, &root),
+        Err(TraversalError::Load { cid, .. }) if cid == missing
+    ));
+
+    let mut blocks = traversal.into_loader();
+    blocks.blocks.insert(missing, vec![0xff]);
+    let mut traversal = Traversal::new(blocks).with_codec::<DagCborCodec>();
+    assert!(matches!(
+        walk(&mut traversal, &root),
+        Err(TraversalError::Decode { cid, .. }) if cid == missing
+    ));
+}
+
+#[test]
+fn test_blockstore_loader() {
+    let mut blocks = Blocks::default();
+    let cids = chain(&mut blocks, 3);
+    let mut store = MemoryBlockstore::new();
+    store.put_many(blocks.blocks).unwrap();
+
+    let mut traversal = Traversal::new(store).with_codec::<DagCborCodec>();
+    let visited = walk(&mut traversal, &cids[2]).unwrap();
+    assert_eq!(visited.last().unwrap().0, "/previous/previous/index");
+
+    let mut store = traversal.into_loader();
+    store.delete(&cids[0]).unwrap();
+    let mut traversal = Traversal::new(store).with_codec::<DagCborCodec>();
+    assert!(matches!(
+        walk(&mut traversal, &cids[2]),
+        Err(TraversalError::Load { cid, .. }) if cid == cids[0]
+    ));
+}
+
+#[test]
+fn test_registry() {
+    use ipld_core::codec::CodecRegistry;
+    use serde_ipld_dagjson::codec::DagJsonCodec;
+
+    let mut blocks = Blocks::default();
+    let data = DagJsonCodec::encode_to_vec(&ipld!("json")).unwrap();
+    let json = cid_of(<DagJsonCodec as Codec<Ipld>>::CODE, &data, 0xff);
+    blocks.blocks.insert(json, data);
+    let root = blocks.add(&ipld!({"json": json}));
+
+    let registry = CodecRegistry::new()
+        .with_codec::<DagCborCodec>()
+        .with_codec::<DagJsonCodec>();
+    let mut traversal = Traversal::new(blocks).with_registry(registry);
+    let visited = walk(&mut traversal, &root).unwrap();
+    assert_eq!(visited[1], ("/json".to_string(), ipld!("json")));
+}
+
+#[test]
+fn test_inline_identity_block() {
+    let mut blocks = Blocks::default();
+    let inline = Cid::new_v1(
+        <RawCodec as Codec<Ipld>>::CODE,
+        Multihash::wrap(0x00, b"inline").unwrap(),
+    );
+    let root = blocks.add(&ipld!({"data": inline}));
+
+    let mut traversal = Traversal::new(blocks)
+        .with_codec::<DagCborCodec>()
+        .with_codec::<RawCodec>()
+        .max_blocks(1);
+    let visited = walk(&mut traversal, &root).unwrap();
+    assert_eq!(visited[1], ("/data".to_string(), ipld!(b"inline".to_vec())));
+    assert_eq!(traversal.into_loader().loaded, [root]);
+}
+
+#[test]
+fn test_default_budgets() {
+    let mut blocks = Blocks::default();
+    let cids = chain(&mut blocks, Traversal::<Blocks>::DEFAULT_MAX_DEPTH + 2);
+    let head = *cids.last().unwrap();
+
+    let mut traversal = Traversal::new(blocks).with_codec::<DagCborCodec>();
+    assert!(matches!(
+        walk(&mut traversal, &head),
+        Err(TraversalError::MaxDepth(
+            Traversal::<Blocks>::DEFAULT_MAX_DEPTH
+        ))
+    ));
+
+    let mut traversal = traversal.max_depth(usize::MAX);
+    assert!(walk(&mut traversal, &head).is_ok());
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn test_verify_hashes() {
+    let mut blocks = Blocks::default();
+    let leaf = blocks.add(&ipld!("leaf"));
+    let root = blocks.add(&ipld!({"leaf": leaf}));
+    blocks
+        .blocks
+        .insert(leaf, DagCborCodec::encode_to_vec(&ipld!("forged")).unwrap());
+
+    let mut traversal = Traversal::new(blocks).with_codec::<DagCborCodec>();
+    assert!(matches!(
+        walk(&mut traversal, &root),
+        Err(TraversalError::Hash { cid, .. }) if cid == leaf
+    ));
+
+    let mut traversal = traversal.verify_hashes(false);
+    let visited = walk(&mut traversal, &root).unwrap();
+    assert_eq!(visited[1], ("/leaf".to_string(), ipld!("forged")));
+}